@@ -0,0 +1,536 @@
+use chaikin::geometry::ring_points;
+use chaikin::{ChaikinCurve, MirrorAxis};
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+
+const ROTATE_STEP_DEGREES: f32 = 1.0;
+const SCALE_UP_FACTOR: f32 = 1.1;
+const SCALE_DOWN_FACTOR: f32 = 0.9;
+// How far (in pixels) simplification may move the polygon's outline
+const SIMPLIFY_EPSILON: f32 = 2.0;
+// The corner preservation threshold moves by this much per step, wrapping
+// through "off" at either end
+const PRESERVE_ANGLE_STEP_DEGREES: f32 = 15.0;
+// With adaptive subdivision, a step that moves the curve less than this
+// (in pixels) counts as converged
+const CONVERGENCE_THRESHOLD: f32 = 1.0;
+// With curvature-adaptive refinement, corners turning by less than this
+// are left unrefined
+const REFINE_ANGLE_DEGREES: f32 = 10.0;
+// How much one press changes the ratio of the segment under the cursor. A
+// ratio of 0 leaves the segment's corners sharp and 0.5 meets in the middle.
+const SEGMENT_RATIO_STEP: f32 = 0.05;
+const MAX_SEGMENT_RATIO: f32 = 0.5;
+// Neighbouring points closer than the merge distance are smoothed as one;
+// it moves by this many pixels per press, from 0 (off) up to the maximum
+const DEDUP_EPSILON_STEP: f32 = 1.0;
+const MAX_DEDUP_EPSILON: f32 = 20.0;
+// Points in a ring dragged out as the starting shape
+pub const RING_POINTS: usize = 8;
+// How far, in pixels, jitter nudges the smoothed points when turned on by key
+pub const JITTER_PIXELS: f32 = 1.5;
+
+// Everything the user can do. Most variants are bound to keys; the ones
+// carrying positions come from the mouse, the arrow keys and the point list.
+// Serializable so a recorded session can be saved and played back.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Action {
+    StartAnimation,
+    Trace,
+    BuildUp,
+    Clear,
+    SetSteps(usize),
+    ReverseDirection,
+    ToggleLooping,
+    ToggleLivePreview,
+    RaiseDedupEpsilon,
+    LowerDedupEpsilon,
+    StepForward,
+    StepBack,
+    ToggleCatchUp,
+    ToggleAllSteps,
+    FewerOverlaidSteps,
+    MoreOverlaidSteps,
+    ToggleClosed,
+    RaiseCornerAngle,
+    LowerCornerAngle,
+    ToggleAdaptive,
+    ToggleRefineBends,
+    RaiseSegmentRatio,
+    LowerSegmentRatio,
+    BumpSegmentRatioAt { x: f32, y: f32, delta: f32 },
+    Simplify,
+    Mirror,
+    MirrorHorizontal,
+    RotateLeft,
+    RotateRight,
+    ScaleUp,
+    ScaleDown,
+    DeleteSelected,
+    ToggleLock,
+    ToggleLockAt { x: f32, y: f32 },
+    ToggleCusp,
+    ToggleCuspAt { x: f32, y: f32 },
+    // `weight` scales the ratio of the segment leading to the new point; 1
+    // leaves it as it is
+    AddPoint { x: f32, y: f32, weight: f32 },
+    AddSnappedPoint { x: f32, y: f32, grid_size: f32, weight: f32 },
+    // A closed ring of `RING_POINTS` points round (`x`, `y`), to start from
+    AddRing { x: f32, y: f32, radius: f32 },
+    // Selects just the point under (`x`, `y`), or nothing if there is none
+    SelectOnlyAt { x: f32, y: f32 },
+    ToggleSelectionAt { x: f32, y: f32 },
+    DeselectAll,
+    MoveSelection { dx: f32, dy: f32 },
+    // Ends a freehand stroke that began with the point at `start`
+    FinishStroke { start: usize },
+    ReorderPoint { from: usize, to: usize },
+    EnterCoordinates,
+    ToggleSnap,
+    ToggleFreehand,
+    ToggleRawClicks,
+    CycleRenderMode,
+    ToggleGlow,
+    ToggleAntialias,
+    ToggleAntialiasPoints,
+    CycleSupersample,
+    ToggleEvenSpacing,
+    ToggleMotion,
+    ToggleLimitCurve,
+    CycleCurveColor,
+    ToggleEditLock,
+    ToggleCompareRatios,
+    ToggleTangents,
+    ToggleHull,
+    ToggleJitter,
+    ToggleLabels,
+    ToggleSegmentLengths,
+    TogglePointList,
+    ToggleMinimap,
+    CycleSelectionStyle,
+    CycleMarkerShape,
+    TogglePointsOnTop,
+    ToggleMarchingAnts,
+    RaiseOverlayOpacity,
+    LowerOverlayOpacity,
+    ToggleFrameLimit,
+    CycleMouseMode,
+    ToggleClampToWindow,
+    ResetView,
+    ToggleSmoothView,
+    FitView,
+    ExportObj,
+    ImportObj,
+    ImportSvg,
+    NextCurveFile,
+    PreviousCurveFile,
+    ExportSteps,
+    ExportStepsCsv,
+    Screenshot,
+    ToggleRecording,
+    PlaySession,
+    PrintCommandLine,
+    ToggleHelp,
+    Quit,
+}
+
+impl Action {
+    // Actions that keep firing while their key is held
+    pub fn repeats(self) -> bool {
+        matches!(
+            self,
+            Action::RotateLeft | Action::RotateRight | Action::ScaleUp | Action::ScaleDown
+        )
+    }
+
+    // Rotating and scaling, which work around the centroid
+    pub fn is_transform(self) -> bool {
+        matches!(
+            self,
+            Action::RotateLeft | Action::RotateRight | Action::ScaleUp | Action::ScaleDown
+        )
+    }
+
+    // Whether a recorded session keeps this action: the edits to the curve
+    // and its settings that `apply_action` carries out. Everything else
+    // (views, files, the recording itself) is left out so playback only
+    // redoes the drawing. Actions aimed at the cursor have to be resolved to
+    // their positioned forms first, or they would hit wherever the cursor
+    // happens to be during playback.
+    pub fn is_recordable(self) -> bool {
+        matches!(
+            self,
+            Action::StartAnimation
+                | Action::Trace
+                | Action::BuildUp
+                | Action::Clear
+                | Action::SetSteps(_)
+                | Action::ReverseDirection
+                | Action::ToggleLooping
+                | Action::ToggleLivePreview
+                | Action::RaiseDedupEpsilon
+                | Action::LowerDedupEpsilon
+                | Action::StepForward
+                | Action::StepBack
+                | Action::ToggleCatchUp
+                | Action::ToggleClosed
+                | Action::RaiseCornerAngle
+                | Action::LowerCornerAngle
+                | Action::ToggleAdaptive
+                | Action::ToggleRefineBends
+                | Action::ToggleJitter
+                | Action::BumpSegmentRatioAt { .. }
+                | Action::Simplify
+                | Action::Mirror
+                | Action::MirrorHorizontal
+                | Action::RotateLeft
+                | Action::RotateRight
+                | Action::ScaleUp
+                | Action::ScaleDown
+                | Action::DeleteSelected
+                | Action::ToggleLockAt { .. }
+                | Action::ToggleCuspAt { .. }
+                | Action::AddPoint { .. }
+                | Action::AddSnappedPoint { .. }
+                | Action::AddRing { .. }
+                | Action::SelectOnlyAt { .. }
+                | Action::ToggleSelectionAt { .. }
+                | Action::DeselectAll
+                | Action::MoveSelection { .. }
+                | Action::FinishStroke { .. }
+                | Action::ReorderPoint { .. }
+        )
+    }
+
+    // The positioned form of an action aimed at whatever is under the
+    // cursor at (`x`, `y`) in curve coordinates; other actions are returned
+    // as they are
+    pub fn at_cursor(self, x: f32, y: f32) -> Self {
+        match self {
+            Action::ToggleLock => Action::ToggleLockAt { x, y },
+            Action::ToggleCusp => Action::ToggleCuspAt { x, y },
+            Action::RaiseSegmentRatio => {
+                Action::BumpSegmentRatioAt { x, y, delta: SEGMENT_RATIO_STEP }
+            }
+            Action::LowerSegmentRatio => {
+                Action::BumpSegmentRatioAt { x, y, delta: -SEGMENT_RATIO_STEP }
+            }
+            other => other,
+        }
+    }
+
+    // Actions that need the cursor's position to mean anything
+    pub fn needs_cursor(self) -> bool {
+        matches!(
+            self,
+            Action::ToggleLock
+                | Action::ToggleCusp
+                | Action::RaiseSegmentRatio
+                | Action::LowerSegmentRatio
+        )
+    }
+
+    // The variant to use while Shift is held, for actions with a second mode
+    pub fn shifted(self) -> Self {
+        match self {
+            Action::Mirror => Action::MirrorHorizontal,
+            Action::RaiseCornerAngle => Action::LowerCornerAngle,
+            Action::RaiseSegmentRatio => Action::LowerSegmentRatio,
+            Action::ImportObj => Action::ImportSvg,
+            Action::ToggleAntialias => Action::ToggleAntialiasPoints,
+            Action::CycleMarkerShape => Action::TogglePointsOnTop,
+            Action::ToggleHull => Action::ToggleJitter,
+            Action::ToggleLabels => Action::ToggleSegmentLengths,
+            Action::ToggleAdaptive => Action::ToggleRefineBends,
+            Action::ToggleTangents => Action::ToggleMarchingAnts,
+            Action::MoreOverlaidSteps => Action::RaiseOverlayOpacity,
+            Action::CycleMouseMode => Action::ToggleClampToWindow,
+            Action::FewerOverlaidSteps => Action::LowerOverlayOpacity,
+            Action::StartAnimation => Action::ToggleLivePreview,
+            Action::Trace => Action::BuildUp,
+            Action::RaiseDedupEpsilon => Action::LowerDedupEpsilon,
+            Action::ToggleRecording => Action::PlaySession,
+            Action::ResetView => Action::ToggleSmoothView,
+            Action::ExportSteps => Action::ExportStepsCsv,
+            other => other,
+        }
+    }
+}
+
+// Applies an action to the curve, returning a message worth showing the
+// user, if any. Actions that only concern the window (view toggles, files,
+// quitting) are left to the caller and do nothing here.
+pub fn apply_action(chaikin: &mut ChaikinCurve, action: Action) -> Option<String> {
+    match action {
+        Action::StartAnimation => chaikin.start_animation(),
+        // Replay the order the points were placed in before smoothing them
+        Action::Trace => chaikin.start_trace(),
+        Action::BuildUp => chaikin.start_build_up(),
+        Action::Clear => chaikin.clear_points(),
+        Action::SetSteps(steps) => chaikin.set_max_steps(steps),
+        Action::ReverseDirection => chaikin.reverse_direction(),
+        Action::ToggleLooping => chaikin.looping = !chaikin.looping,
+        Action::ToggleLivePreview => {
+            chaikin.live_preview = !chaikin.live_preview;
+            return Some(if chaikin.live_preview {
+                "Live preview: the polygon is shown smoothed while editing".to_string()
+            } else {
+                "Live preview off".to_string()
+            });
+        }
+        Action::StepForward => chaikin.step_forward(),
+        Action::StepBack => chaikin.step_back(),
+        Action::ToggleCatchUp => {
+            chaikin.catch_up = !chaikin.catch_up;
+            return Some(if chaikin.catch_up {
+                "Steps follow the clock, catching up after slow frames".to_string()
+            } else {
+                "One step per frame at most".to_string()
+            });
+        }
+        Action::ToggleClosed => chaikin.toggle_closed(),
+        Action::RaiseCornerAngle | Action::LowerCornerAngle => {
+            let steps = (180.0 / PRESERVE_ANGLE_STEP_DEGREES) as i32;
+            let current = chaikin.preserve_angle.map_or(0, |angle| {
+                (angle.to_degrees() / PRESERVE_ANGLE_STEP_DEGREES).round() as i32
+            });
+            let step = if action == Action::LowerCornerAngle { -1 } else { 1 };
+            let next = (current + step).rem_euclid(steps);
+            let message = if next == 0 {
+                chaikin.preserve_angle = None;
+                "Corner preservation off".to_string()
+            } else {
+                let degrees = next as f32 * PRESERVE_ANGLE_STEP_DEGREES;
+                chaikin.preserve_angle = Some(degrees.to_radians());
+                format!("Keeping corners sharper than {} degrees", degrees)
+            };
+            regenerate(chaikin);
+            return Some(message);
+        }
+        // Switch between a fixed number of steps and stopping once converged
+        Action::ToggleAdaptive => {
+            chaikin.convergence_threshold = match chaikin.convergence_threshold {
+                Some(_) => None,
+                None => Some(CONVERGENCE_THRESHOLD),
+            };
+            regenerate(chaikin);
+            return Some(if chaikin.convergence_threshold.is_some() {
+                "Adaptive subdivision: stop once converged".to_string()
+            } else {
+                "Fixed subdivision: always run every step".to_string()
+            });
+        }
+        // Each time it comes on it draws a new seed, so toggling twice is a
+        // reroll. The next seed follows from the last, keeping replays exact.
+        Action::ToggleJitter => {
+            if chaikin.jitter > 0.0 {
+                chaikin.jitter = 0.0;
+            } else {
+                chaikin.jitter = JITTER_PIXELS;
+                chaikin.jitter_seed = chaikin.jitter_seed.wrapping_add(1);
+            }
+            regenerate(chaikin);
+            return Some(if chaikin.jitter > 0.0 {
+                format!("Jitter of {} pixels, seed {}", chaikin.jitter, chaikin.jitter_seed)
+            } else {
+                "Jitter off".to_string()
+            });
+        }
+        // Refine every corner, or only the ones that bend noticeably
+        Action::ToggleRefineBends => {
+            chaikin.refine_angle = match chaikin.refine_angle {
+                Some(_) => None,
+                None => Some(REFINE_ANGLE_DEGREES.to_radians()),
+            };
+            regenerate(chaikin);
+            return Some(match chaikin.refine_angle {
+                Some(_) => format!(
+                    "Refining only corners that turn by {} degrees or more",
+                    REFINE_ANGLE_DEGREES
+                ),
+                None => "Refining every corner".to_string(),
+            });
+        }
+        Action::BumpSegmentRatioAt { x, y, delta } => {
+            let segment = chaikin.nearest_segment(x, y)?;
+            let ratio = (chaikin.segment_ratio(segment) + delta).clamp(0.0, MAX_SEGMENT_RATIO);
+            chaikin.set_segment_ratio(segment, ratio);
+            regenerate(chaikin);
+            return Some(format!("Segment {} ratio {:.2}", segment, ratio));
+        }
+        Action::RaiseDedupEpsilon | Action::LowerDedupEpsilon => {
+            let step = if action == Action::LowerDedupEpsilon {
+                -DEDUP_EPSILON_STEP
+            } else {
+                DEDUP_EPSILON_STEP
+            };
+            chaikin.dedup_epsilon = (chaikin.dedup_epsilon + step).clamp(0.0, MAX_DEDUP_EPSILON);
+            regenerate(chaikin);
+            return Some(if chaikin.dedup_epsilon > 0.0 {
+                format!("Merging points closer than {} px", chaikin.dedup_epsilon)
+            } else {
+                "Point merging off".to_string()
+            });
+        }
+        Action::Simplify => {
+            let before = chaikin.control_points.len();
+            chaikin.simplify(SIMPLIFY_EPSILON);
+            return Some(format!(
+                "Simplified control points: {} -> {}",
+                before,
+                chaikin.control_points.len()
+            ));
+        }
+        // Mirrors across the vertical (or horizontal) line through the last
+        // point, so the copy joins on where the drawing ends
+        Action::Mirror | Action::MirrorHorizontal => {
+            let last = chaikin.control_points.last()?.position;
+            let axis = if action == Action::MirrorHorizontal {
+                MirrorAxis::Horizontal(last.y)
+            } else {
+                MirrorAxis::Vertical(last.x)
+            };
+            if !chaikin.mirror_across(axis) {
+                return Some("Mirroring would exceed the point limit".to_string());
+            }
+        }
+        Action::RotateLeft => chaikin.rotate_all(-ROTATE_STEP_DEGREES.to_radians()),
+        Action::RotateRight => chaikin.rotate_all(ROTATE_STEP_DEGREES.to_radians()),
+        Action::ScaleUp => {
+            chaikin.scale_all(SCALE_UP_FACTOR);
+        }
+        Action::ScaleDown => {
+            chaikin.scale_all(SCALE_DOWN_FACTOR);
+        }
+        Action::DeleteSelected => chaikin.delete_selected(),
+        // Pin the point so it can't be dragged or deleted
+        Action::ToggleLockAt { x, y } => {
+            chaikin.toggle_lock_at(x, y);
+        }
+        // Break the curve at the point instead of smoothing through it
+        Action::ToggleCuspAt { x, y } => {
+            chaikin.toggle_cusp_at(x, y);
+        }
+        Action::AddPoint { x, y, weight } => {
+            let added = chaikin.add_point(x, y);
+            return after_add(chaikin, added, weight);
+        }
+        Action::AddSnappedPoint { x, y, grid_size, weight } => {
+            let added = chaikin.add_point_snapped(x, y, grid_size);
+            return after_add(chaikin, added, weight);
+        }
+        Action::AddRing { x, y, radius } => {
+            // All of the ring or none of it, never an unclosed part
+            if let Some(max) = chaikin.max_control_points
+                && chaikin.control_points.len() + RING_POINTS > max
+            {
+                return Some(point_limit_notice(max));
+            }
+            for point in ring_points(Point2::new(x, y), radius, RING_POINTS) {
+                chaikin.add_point(point.x, point.y);
+            }
+            chaikin.closed = true;
+            chaikin.reset_animation();
+        }
+        Action::SelectOnlyAt { x, y } => {
+            chaikin.deselect_all_points();
+            chaikin.select_point_at(x, y);
+        }
+        Action::ToggleSelectionAt { x, y } => {
+            chaikin.toggle_selection_at(x, y);
+        }
+        Action::DeselectAll => chaikin.deselect_all_points(),
+        Action::MoveSelection { dx, dy } => {
+            chaikin.translate_selected_points(dx, dy);
+            regenerate(chaikin);
+        }
+        Action::ReorderPoint { from, to } => chaikin.move_point(from, to),
+        // A stroke drops a point every few pixels; thin it out to the ones
+        // that carry its shape
+        Action::FinishStroke { start } => {
+            let before = chaikin.control_points.len().saturating_sub(start);
+            chaikin.simplify_from(start, SIMPLIFY_EPSILON);
+            let after = chaikin.control_points.len().saturating_sub(start);
+            return Some(format!("Stroke simplified: {} -> {} points", before, after));
+        }
+        _ => {}
+    }
+    None
+}
+
+// Starts over from the grown control polygon, or explains why the point
+// wasn't added
+fn after_add(chaikin: &mut ChaikinCurve, added: bool, weight: f32) -> Option<String> {
+    if added {
+        let count = chaikin.control_points.len();
+        if weight != 1.0 && count >= 2 {
+            let segment = count - 2;
+            let ratio = chaikin.segment_ratio(segment) * weight;
+            chaikin.set_segment_ratio(segment, ratio.clamp(0.0, MAX_SEGMENT_RATIO));
+        }
+        chaikin.reset_animation();
+        None
+    } else {
+        chaikin.max_control_points.map(point_limit_notice)
+    }
+}
+
+fn point_limit_notice(max: usize) -> String {
+    format!("Point limit reached ({} points)", max)
+}
+
+// Keeps a running animation in step with whatever was just changed
+fn regenerate(chaikin: &mut ChaikinCurve) {
+    if chaikin.animating {
+        chaikin.generate_animation_steps();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripted_actions_build_and_edit_a_curve() {
+        let mut chaikin = ChaikinCurve::new();
+        let script = [
+            Action::AddPoint { x: 0.0, y: 0.0, weight: 1.0 },
+            Action::AddPoint { x: 100.0, y: 0.0, weight: 1.0 },
+            Action::AddPoint { x: 100.0, y: 100.0, weight: 1.0 },
+            Action::SelectOnlyAt { x: 100.0, y: 0.0 },
+            Action::MoveSelection { dx: 10.0, dy: 0.0 },
+            Action::ToggleClosed,
+            Action::StartAnimation,
+        ];
+        for action in script {
+            apply_action(&mut chaikin, action);
+        }
+        let positions: Vec<_> = chaikin.control_points.iter().map(|cp| cp.position).collect();
+        assert_eq!(
+            positions,
+            [Point2::new(0.0, 0.0), Point2::new(110.0, 0.0), Point2::new(100.0, 100.0)]
+        );
+        assert!(chaikin.closed && chaikin.animating);
+        assert_eq!(chaikin.animation_steps.len(), chaikin.max_steps + 1);
+
+        apply_action(&mut chaikin, Action::DeleteSelected);
+        assert_eq!(chaikin.control_points.len(), 2);
+        apply_action(&mut chaikin, Action::Clear);
+        assert!(chaikin.control_points.is_empty());
+    }
+
+    #[test]
+    fn ring_past_the_point_limit_adds_nothing() {
+        let mut chaikin = ChaikinCurve::new();
+        chaikin.max_control_points = Some(RING_POINTS - 1);
+        let ring = Action::AddRing { x: 50.0, y: 50.0, radius: 40.0 };
+        let notice = apply_action(&mut chaikin, ring);
+        assert_eq!(notice, Some(point_limit_notice(RING_POINTS - 1)));
+        assert!(chaikin.control_points.is_empty() && !chaikin.closed);
+
+        chaikin.max_control_points = Some(RING_POINTS);
+        assert_eq!(apply_action(&mut chaikin, ring), None);
+        assert_eq!(chaikin.control_points.len(), RING_POINTS);
+        assert!(chaikin.closed);
+    }
+}
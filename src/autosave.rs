@@ -0,0 +1,65 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chaikin::ChaikinCurve;
+use nalgebra::Point2;
+
+pub const RECOVERY_FILE: &str = "chaikin_recovery.json";
+// Rapid drags change the points every frame; don't write more often than this
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+// Keeps a recovery copy of the control points on disk, rewritten whenever
+// they have changed since the last save
+pub struct AutoSave {
+    last_save: Instant,
+    saved: Vec<Point2<f32>>,
+}
+
+impl AutoSave {
+    pub fn new(chaikin: &ChaikinCurve) -> Self {
+        Self {
+            last_save: Instant::now(),
+            saved: positions(chaikin),
+        }
+    }
+
+    pub fn recovery_available() -> bool {
+        Path::new(RECOVERY_FILE).exists()
+    }
+
+    pub fn update(&mut self, chaikin: &ChaikinCurve) {
+        if self.last_save.elapsed() < AUTOSAVE_INTERVAL {
+            return;
+        }
+
+        let current = positions(chaikin);
+        if current == self.saved {
+            return;
+        }
+
+        if let Err(err) = chaikin.save_points(RECOVERY_FILE) {
+            eprintln!("Auto-save to {} failed: {}", RECOVERY_FILE, err);
+        }
+        // Even on failure, wait a full interval before trying again
+        self.last_save = Instant::now();
+        self.saved = current;
+    }
+
+    // Writes the points out right away, for quitting. A failure is only
+    // reported, so it never keeps the app from closing.
+    pub fn save_now(&mut self, chaikin: &ChaikinCurve) {
+        match chaikin.save_points(RECOVERY_FILE) {
+            Ok(()) => println!(
+                "Saved {} points to {}",
+                chaikin.control_points.len(),
+                RECOVERY_FILE
+            ),
+            Err(err) => eprintln!("Could not save {} on exit: {}", RECOVERY_FILE, err),
+        }
+        self.saved = positions(chaikin);
+    }
+}
+
+fn positions(chaikin: &ChaikinCurve) -> Vec<Point2<f32>> {
+    chaikin.control_points.iter().map(|cp| cp.position).collect()
+}
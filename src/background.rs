@@ -0,0 +1,110 @@
+use std::io;
+use std::path::Path;
+
+use image::{ImageError, ImageReader};
+use nalgebra::Point2;
+
+use crate::draw::Canvas;
+use crate::viewport::Viewport;
+
+// How bright the reference image is kept, so the curve stands out over it
+const BRIGHTNESS: f32 = 0.4;
+
+// A reference image drawn dimmed behind everything else, for tracing over.
+// It is fitted into the window once, in curve coordinates, so it pans and
+// zooms along with the points traced over it.
+pub struct Background {
+    width: usize,
+    height: usize,
+    // Already dimmed, as 0RGB
+    pixels: Vec<u32>,
+    // Curve coordinates of the image's top left corner, and the size of one
+    // image pixel in them
+    origin: Point2<f32>,
+    pixel_size: f32,
+}
+
+impl Background {
+    // Reads a PNG or JPEG and fits it into a `width` x `height` window,
+    // centred and keeping its proportions. Transparent parts fade to the
+    // black background.
+    pub fn load(path: impl AsRef<Path>, width: usize, height: usize) -> io::Result<Self> {
+        // Recognised by its contents, whatever the file is called
+        let image = ImageReader::open(path)?
+            .with_guessed_format()?
+            .decode()
+            .map_err(|err| match err {
+                ImageError::IoError(err) => err,
+                err => io::Error::new(io::ErrorKind::InvalidData, err),
+            })?
+            .into_rgba8();
+        let pixels = image
+            .pixels()
+            .map(|pixel| {
+                let [r, g, b, alpha] = pixel.0;
+                let scale = BRIGHTNESS * alpha as f32 / 255.0;
+                [r, g, b]
+                    .iter()
+                    .fold(0, |color, &c| (color << 8) | (c as f32 * scale).round() as u32)
+            })
+            .collect();
+
+        let (image_width, image_height) = (image.width() as usize, image.height() as usize);
+        let pixel_size =
+            (width as f32 / image_width as f32).min(height as f32 / image_height as f32);
+        let origin = Point2::new(
+            (width as f32 - image_width as f32 * pixel_size) / 2.0,
+            (height as f32 - image_height as f32 * pixel_size) / 2.0,
+        );
+        Ok(Self {
+            width: image_width,
+            height: image_height,
+            pixels,
+            origin,
+            pixel_size,
+        })
+    }
+
+    // Fills the canvas with the image as seen through `view`, taking the
+    // nearest image pixel for each canvas pixel
+    pub fn draw(&self, canvas: &mut Canvas, view: &Viewport) {
+        for (y, row) in canvas.pixels.chunks_exact_mut(canvas.width).enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let world = view.screen_to_world(Point2::new(x as f32 + 0.5, y as f32 + 0.5));
+                let image = (world - self.origin) / self.pixel_size;
+                if image.x >= 0.0 && image.y >= 0.0 {
+                    let (ix, iy) = (image.x as usize, image.y as usize);
+                    if ix < self.width && iy < self.height {
+                        *pixel = self.pixels[iy * self.width + ix];
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{ImageFormat, Rgb, RgbImage};
+
+    use super::*;
+
+    #[test]
+    fn jpeg_loads_dimmed_and_fitted() {
+        let image = RgbImage::from_pixel(20, 10, Rgb([200, 100, 50]));
+        // No extension, so the format has to come from the contents
+        let path = std::env::temp_dir().join(format!("chaikin-trace-{}", std::process::id()));
+        image.save_with_format(&path, ImageFormat::Jpeg).expect("saved");
+        let background = Background::load(&path, 100, 100);
+        let _ = std::fs::remove_file(&path);
+        let background = background.expect("loaded");
+
+        assert_eq!((background.width, background.height), (20, 10));
+        // Fitted across the window's width and centred down it
+        assert_eq!(background.pixel_size, 5.0);
+        assert_eq!(background.origin, Point2::new(0.0, 25.0));
+        // Lossy, so only roughly the colour at 40 percent
+        let [r, g, b] = [16, 8, 0].map(|shift| (background.pixels[0] >> shift) & 0xFF);
+        assert!(r.abs_diff(80) <= 4 && g.abs_diff(40) <= 4 && b.abs_diff(20) <= 4);
+    }
+}
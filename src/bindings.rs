@@ -0,0 +1,300 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use minifb::Key;
+
+use crate::actions::Action;
+
+// Optional key map read at startup, one `action = Key, Key...` line per
+// action, for example `clear = Q` or `toggle_help = H, F1`. Actions not
+// mentioned keep their default keys, and `action =` with no keys unbinds it.
+pub const BINDINGS_FILE: &str = "chaikin_keys.conf";
+
+// Every action in the order the help overlay lists them, with its name in
+// the bindings file and its help text
+const ACTIONS: &[(Action, &str, &str)] = &[
+    (Action::StartAnimation, "start_animation", "Start the animation (Shift: preview)"),
+    (Action::ToggleLivePreview, "toggle_live_preview", "Preview the smoothed curve"),
+    (Action::RaiseDedupEpsilon, "raise_dedup_epsilon", "Raise the merge distance (Shift: lower)"),
+    (Action::LowerDedupEpsilon, "lower_dedup_epsilon", "Lower the merge distance"),
+    (Action::Trace, "trace", "Replay point placement (Shift: build up)"),
+    (Action::BuildUp, "build_up", "Grow the curve one point at a time"),
+    (Action::Clear, "clear", "Clear all points"),
+    (Action::SetSteps(1), "steps_1", "Number of subdivision steps"),
+    (Action::SetSteps(2), "steps_2", "Number of subdivision steps"),
+    (Action::SetSteps(3), "steps_3", "Number of subdivision steps"),
+    (Action::SetSteps(4), "steps_4", "Number of subdivision steps"),
+    (Action::SetSteps(5), "steps_5", "Number of subdivision steps"),
+    (Action::SetSteps(6), "steps_6", "Number of subdivision steps"),
+    (Action::SetSteps(7), "steps_7", "Number of subdivision steps"),
+    (Action::SetSteps(8), "steps_8", "Number of subdivision steps"),
+    (Action::SetSteps(9), "steps_9", "Number of subdivision steps"),
+    (Action::ReverseDirection, "reverse", "Reverse the animation"),
+    (Action::ToggleLooping, "toggle_looping", "Toggle looping"),
+    (Action::StepForward, "step_forward", "Show the next step"),
+    (Action::StepBack, "step_back", "Show the previous step"),
+    (Action::ToggleCatchUp, "toggle_catch_up", "Catch up on late steps"),
+    (Action::ToggleAllSteps, "toggle_all_steps", "Overlay earlier steps"),
+    (Action::FewerOverlaidSteps, "fewer_overlaid_steps", "Fewer overlaid steps (Shift: fainter)"),
+    (Action::MoreOverlaidSteps, "more_overlaid_steps", "More overlaid steps (Shift: stronger)"),
+    (Action::LowerOverlayOpacity, "lower_overlay_opacity", "Fade the overlays"),
+    (Action::RaiseOverlayOpacity, "raise_overlay_opacity", "Strengthen the overlays"),
+    (Action::ToggleClosed, "toggle_closed", "Toggle a closed loop"),
+    (Action::RaiseCornerAngle, "raise_corner_angle", "Raise the corner angle (Shift: lower)"),
+    (Action::LowerCornerAngle, "lower_corner_angle", "Lower the corner angle"),
+    (Action::ToggleAdaptive, "toggle_adaptive", "Stop once converged (Shift: refine bends only)"),
+    (Action::ToggleRefineBends, "toggle_refine_bends", "Refine only the bends"),
+    (Action::RaiseSegmentRatio, "raise_segment_ratio", "Raise this segment's ratio (Shift: lower)"),
+    (Action::LowerSegmentRatio, "lower_segment_ratio", "Lower the segment ratio here"),
+    (Action::Simplify, "simplify", "Simplify the points"),
+    (Action::Mirror, "mirror", "Mirror (Shift: horizontally)"),
+    (Action::MirrorHorizontal, "mirror_horizontal", "Mirror horizontally"),
+    (Action::RotateLeft, "rotate_left", "Rotate all points left"),
+    (Action::RotateRight, "rotate_right", "Rotate all points right"),
+    (Action::ScaleUp, "scale_up", "Scale all points up"),
+    (Action::ScaleDown, "scale_down", "Scale all points down"),
+    (Action::DeleteSelected, "delete_selected", "Delete the selected points"),
+    (Action::EnterCoordinates, "enter_coordinates", "Type the coordinates of a point"),
+    (Action::ToggleLock, "toggle_lock", "Lock the point under the cursor"),
+    (Action::ToggleCusp, "toggle_cusp", "Make the point under the cursor a cusp"),
+    (Action::ToggleSnap, "toggle_snap", "Toggle grid snapping"),
+    (Action::ToggleFreehand, "toggle_freehand", "Freehand drawing (drag to sketch)"),
+    (Action::ToggleRawClicks, "toggle_raw_clicks", "Show raw click positions"),
+    (Action::CycleRenderMode, "cycle_render_mode", "Cycle the render mode"),
+    (Action::ToggleGlow, "toggle_glow", "Toggle the glow"),
+    (Action::ToggleAntialias, "toggle_antialias", "Anti-alias the curve (Shift: points)"),
+    (Action::ToggleAntialiasPoints, "toggle_antialias_points", "Anti-alias the control points"),
+    (Action::CycleSupersample, "cycle_supersample", "Supersampling: off, 2x, 4x"),
+    (Action::ToggleEvenSpacing, "toggle_even_spacing", "Space the drawn points evenly"),
+    (Action::ToggleMotion, "toggle_motion", "Arrows to the next step's points"),
+    (Action::ToggleLimitCurve, "toggle_limit_curve", "Show the limit curve"),
+    (Action::CycleCurveColor, "cycle_curve_color", "Change the curve color"),
+    (Action::ToggleEditLock, "toggle_edit_lock", "Lock points while animating"),
+    (Action::ToggleCompareRatios, "toggle_compare_ratios", "Compare smoothing ratios"),
+    (Action::ToggleTangents, "toggle_tangents", "Show tangents (Shift: marching ants)"),
+    (Action::ToggleMarchingAnts, "toggle_marching_ants", "Draw the curve as marching ants"),
+    (Action::ToggleHull, "toggle_hull", "Show the convex hull (Shift: jitter)"),
+    (Action::ToggleJitter, "toggle_jitter", "Jitter the smoothed points"),
+    (Action::ToggleLabels, "toggle_labels", "Show point labels (Shift: segment lengths)"),
+    (Action::ToggleSegmentLengths, "toggle_segment_lengths", "Show segment lengths"),
+    (Action::TogglePointList, "toggle_point_list", "Point list (drag to reorder)"),
+    (Action::ToggleMinimap, "toggle_minimap", "Minimap (click to go there)"),
+    (Action::CycleSelectionStyle, "cycle_selection_style", "Selection highlight style"),
+    (Action::CycleMarkerShape, "cycle_marker_shape", "Cycle the marker shape (Shift: on top)"),
+    (Action::TogglePointsOnTop, "toggle_points_on_top", "Draw the points over the curve"),
+    (Action::ToggleFrameLimit, "toggle_frame_limit", "Toggle the frame limit"),
+    (Action::CycleMouseMode, "cycle_mouse_mode", "Cycle mouse mode off-window (Shift: clamp)"),
+    (Action::ToggleClampToWindow, "toggle_clamp_to_window", "Keep points inside the window"),
+    (Action::ResetView, "reset_view", "Reset the view (Shift: smooth zoom)"),
+    (Action::ToggleSmoothView, "toggle_smooth_view", "Ease zooming and fitting the view"),
+    (Action::FitView, "fit_view", "Bring every point into view"),
+    (Action::ExportObj, "export_obj", "Export to OBJ (Shift: curve)"),
+    (Action::ImportObj, "import_obj", "Import points from OBJ (Shift: SVG)"),
+    (Action::ImportSvg, "import_svg", "Import points from an SVG path"),
+    (Action::NextCurveFile, "next_curve_file", "Next saved curve in this folder"),
+    (Action::PreviousCurveFile, "previous_curve_file", "Previous saved curve"),
+    (Action::ToggleRecording, "toggle_recording", "Record a session (Shift: play back)"),
+    (Action::PlaySession, "play_session", "Play back the recorded session"),
+    (Action::ExportSteps, "export_steps", "Export every step as JSON (Shift: CSV)"),
+    (Action::ExportStepsCsv, "export_steps_csv", "Export point counts and lengths as CSV"),
+    (Action::Screenshot, "screenshot", "Save the screen as a PNG"),
+    (Action::PrintCommandLine, "print_command_line", "Print a command line"),
+    (Action::ToggleHelp, "toggle_help", "Toggle this help"),
+    (Action::Quit, "quit", "Quit"),
+];
+
+const DEFAULT_BINDINGS: &[(Key, Action)] = &[
+    (Key::Enter, Action::StartAnimation),
+    (Key::F5, Action::Trace),
+    (Key::C, Action::Clear),
+    (Key::Key1, Action::SetSteps(1)),
+    (Key::Key2, Action::SetSteps(2)),
+    (Key::Key3, Action::SetSteps(3)),
+    (Key::Key4, Action::SetSteps(4)),
+    (Key::Key5, Action::SetSteps(5)),
+    (Key::Key6, Action::SetSteps(6)),
+    (Key::Key7, Action::SetSteps(7)),
+    (Key::Key8, Action::SetSteps(8)),
+    (Key::Key9, Action::SetSteps(9)),
+    (Key::B, Action::ReverseDirection),
+    (Key::L, Action::ToggleLooping),
+    (Key::Apostrophe, Action::StepForward),
+    (Key::Semicolon, Action::StepBack),
+    (Key::Q, Action::ToggleCatchUp),
+    (Key::A, Action::ToggleAllSteps),
+    (Key::LeftBracket, Action::FewerOverlaidSteps),
+    (Key::RightBracket, Action::MoreOverlaidSteps),
+    (Key::K, Action::ToggleClosed),
+    (Key::Y, Action::RaiseCornerAngle),
+    (Key::E, Action::ToggleAdaptive),
+    (Key::U, Action::RaiseSegmentRatio),
+    (Key::X, Action::Simplify),
+    (Key::M, Action::Mirror),
+    (Key::Comma, Action::RotateLeft),
+    (Key::Period, Action::RotateRight),
+    (Key::Equal, Action::ScaleUp),
+    (Key::Minus, Action::ScaleDown),
+    (Key::Delete, Action::DeleteSelected),
+    (Key::Insert, Action::EnterCoordinates),
+    (Key::F, Action::ToggleLock),
+    (Key::R, Action::ToggleCusp),
+    (Key::G, Action::ToggleSnap),
+    (Key::S, Action::ToggleFreehand),
+    (Key::F8, Action::ToggleRawClicks),
+    (Key::P, Action::CycleRenderMode),
+    (Key::W, Action::ToggleGlow),
+    (Key::F6, Action::ToggleAntialias),
+    (Key::F10, Action::CycleSupersample),
+    (Key::F11, Action::ToggleEvenSpacing),
+    (Key::F12, Action::Screenshot),
+    (Key::Backslash, Action::ToggleMotion),
+    (Key::Backquote, Action::ToggleLimitCurve),
+    (Key::End, Action::CycleCurveColor),
+    (Key::F1, Action::ToggleEditLock),
+    (Key::Backspace, Action::RaiseDedupEpsilon),
+    (Key::Space, Action::ToggleRecording),
+    (Key::F7, Action::ToggleCompareRatios),
+    (Key::T, Action::ToggleTangents),
+    (Key::J, Action::ToggleHull),
+    (Key::N, Action::ToggleLabels),
+    (Key::Tab, Action::TogglePointList),
+    (Key::Z, Action::ToggleMinimap),
+    (Key::F4, Action::CycleSelectionStyle),
+    (Key::D, Action::CycleMarkerShape),
+    (Key::V, Action::ToggleFrameLimit),
+    (Key::F2, Action::CycleMouseMode),
+    (Key::Key0, Action::ResetView),
+    (Key::Home, Action::FitView),
+    (Key::O, Action::ExportObj),
+    (Key::I, Action::ImportObj),
+    (Key::PageDown, Action::NextCurveFile),
+    (Key::PageUp, Action::PreviousCurveFile),
+    (Key::F9, Action::ExportSteps),
+    (Key::F3, Action::PrintCommandLine),
+    (Key::H, Action::ToggleHelp),
+    // '?' on most layouts
+    (Key::Slash, Action::ToggleHelp),
+    (Key::Escape, Action::Quit),
+];
+
+// Keys that can appear in the bindings file, under their minifb names
+const NAMED_KEYS: &[Key] = &[
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I,
+    Key::J, Key::K, Key::L, Key::M, Key::N, Key::O, Key::P, Key::Q, Key::R,
+    Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4,
+    Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6,
+    Key::F7, Key::F8, Key::F9, Key::F10, Key::F11, Key::F12,
+    Key::Comma, Key::Period, Key::Slash, Key::Semicolon, Key::Apostrophe,
+    Key::LeftBracket, Key::RightBracket, Key::Backslash, Key::Minus, Key::Equal,
+    Key::Backquote, Key::Space, Key::Tab, Key::Enter, Key::Escape, Key::Backspace,
+    Key::Delete, Key::Insert, Key::Home, Key::End, Key::PageUp, Key::PageDown,
+];
+
+impl Action {
+    fn from_name(name: &str) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .find(|(_, action_name, _)| *action_name == name)
+            .map(|(action, _, _)| *action)
+    }
+}
+
+fn key_from_name(name: &str) -> Option<Key> {
+    NAMED_KEYS
+        .iter()
+        .copied()
+        .find(|key| format!("{:?}", key).eq_ignore_ascii_case(name))
+}
+
+// How a key is written in the help overlay: digits without the "Key" prefix
+fn key_label(key: Key) -> String {
+    let name = format!("{:?}", key);
+    match name.strip_prefix("Key") {
+        Some(digit) if !digit.is_empty() => digit.to_string(),
+        _ => name,
+    }
+}
+
+pub fn default_bindings() -> HashMap<Key, Action> {
+    DEFAULT_BINDINGS.iter().copied().collect()
+}
+
+// The defaults with the bindings file applied on top, or just the defaults if
+// there is no file. Lines that can't be understood are reported and skipped.
+pub fn load_bindings(path: impl AsRef<Path>) -> HashMap<Key, Action> {
+    let mut bindings = default_bindings();
+    let path = path.as_ref();
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return bindings,
+        Err(err) => {
+            eprintln!("Could not read {}: {}", path.display(), err);
+            return bindings;
+        }
+    };
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        let report = |message: String| {
+            eprintln!("{}:{}: {}", path.display(), number + 1, message);
+        };
+
+        let Some((name, keys)) = line.split_once('=') else {
+            report(format!("expected 'action = Key', got '{}'", line));
+            continue;
+        };
+        let Some(action) = Action::from_name(name.trim()) else {
+            report(format!("unknown action '{}'", name.trim()));
+            continue;
+        };
+        let keys: Result<Vec<Key>, String> = keys
+            .split(',')
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .map(|key| key_from_name(key).ok_or_else(|| format!("unknown key '{}'", key)))
+            .collect();
+        match keys {
+            Ok(keys) => {
+                bindings.retain(|_, bound| *bound != action);
+                for key in keys {
+                    bindings.insert(key, action);
+                }
+            }
+            Err(message) => report(message),
+        }
+    }
+    bindings
+}
+
+// (keys, description) rows for the help overlay, one per description in the
+// order of ACTIONS, skipping anything left without a key
+pub fn help_rows(bindings: &HashMap<Key, Action>) -> Vec<(String, &'static str)> {
+    let mut rows: Vec<(Vec<String>, &'static str)> = Vec::new();
+    for (action, _, description) in ACTIONS {
+        let mut keys: Vec<String> = bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| key_label(*key))
+            .collect();
+        keys.sort();
+        match rows.last_mut() {
+            // Consecutive actions sharing a description (the step counts)
+            // share a row
+            Some((row_keys, row_description)) if row_description == description => {
+                row_keys.extend(keys)
+            }
+            _ => rows.push((keys, description)),
+        }
+    }
+    rows.into_iter()
+        .filter(|(keys, _)| !keys.is_empty())
+        .map(|(keys, description)| (keys.join(" "), description))
+        .collect()
+}
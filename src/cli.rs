@@ -0,0 +1,222 @@
+use std::env;
+use std::path::PathBuf;
+
+use chaikin::STEP_LIMIT;
+
+const USAGE: &str = "Usage: chaikin [--recover] [--input FILE] [--iterations N] [--max-points N]
+               [--closed] [--ratio R] [--replay] [--save-on-exit]
+               [--pressure-from-speed] [--random N] [--seed S] [--profile RUNS]
+               [--trace IMAGE] [--normalize] [--jitter PIXELS] [--jitter-seed S]
+
+Options:
+  --recover         Restore the points from the last auto-saved session
+  --input FILE      Load control points from a JSON file saved by the app
+  --iterations N    Number of subdivision steps to generate (0-12)
+  --max-points N    Refuse to place more than N control points
+  --closed          Smooth the points as a closed loop
+  --ratio R         Where segments are cut, between 0 and 0.5 (default 0.25)
+  --replay          Reveal the loaded points one by one, then animate them
+  --save-on-exit    Save the points to the recovery file when quitting
+  --pressure-from-speed
+                    Round freehand strokes off more where they are drawn slowly
+  --random N        Start from N random points (at least 3) smoothed as a closed blob
+  --seed S          Seed for --random, to make the same blob again
+  --profile RUNS    Time generating the steps RUNS times and print the timings,
+                    without opening a window
+  --trace IMAGE     Show a PNG or JPEG dimmed behind the curve, to trace over
+  --normalize       Save and export points scaled into the unit square
+  --jitter PIXELS   Nudge the smoothed points by up to PIXELS, for a hand-drawn look
+  --jitter-seed S   Seed for --jitter, to nudge the points the same way again";
+
+#[derive(Debug, Default)]
+pub struct Args {
+    pub recover: bool,
+    pub input: Option<PathBuf>,
+    pub iterations: Option<usize>,
+    pub max_points: Option<usize>,
+    pub closed: bool,
+    pub ratio: Option<f32>,
+    pub replay: bool,
+    pub save_on_exit: bool,
+    pub pressure_from_speed: bool,
+    pub random: Option<usize>,
+    pub seed: Option<u64>,
+    pub profile: Option<usize>,
+    pub trace: Option<PathBuf>,
+    pub normalize: bool,
+    pub jitter: Option<f32>,
+    pub jitter_seed: Option<u64>,
+}
+
+impl Args {
+    pub fn parse() -> Result<Self, String> {
+        let mut args = Args::default();
+        let mut argv = env::args().skip(1);
+        while let Some(arg) = argv.next() {
+            let mut value = |name: &str| {
+                argv.next()
+                    .ok_or_else(|| format!("{} needs a value\n\n{}", name, USAGE))
+            };
+            match arg.as_str() {
+                "--recover" => args.recover = true,
+                "--input" => args.input = Some(PathBuf::from(value(&arg)?)),
+                "--iterations" => {
+                    let iterations = number(&arg, value(&arg)?)?;
+                    if iterations > STEP_LIMIT {
+                        return Err(format!(
+                            "--iterations must be at most {} (each step doubles the point count)",
+                            STEP_LIMIT
+                        ));
+                    }
+                    args.iterations = Some(iterations);
+                }
+                "--max-points" => args.max_points = Some(number(&arg, value(&arg)?)?),
+                "--closed" => args.closed = true,
+                "--ratio" => {
+                    let ratio = value(&arg)?;
+                    match ratio.parse::<f32>() {
+                        Ok(r) if r > 0.0 && r < 0.5 => args.ratio = Some(r),
+                        _ => {
+                            return Err(format!(
+                                "--ratio expects a number between 0 and 0.5, got '{}'",
+                                ratio
+                            ));
+                        }
+                    }
+                }
+                "--replay" => args.replay = true,
+                "--save-on-exit" => args.save_on_exit = true,
+                "--pressure-from-speed" => args.pressure_from_speed = true,
+                "--random" => {
+                    let count = number(&arg, value(&arg)?)?;
+                    if count < 3 {
+                        return Err("--random needs at least 3 points to make a blob".to_string());
+                    }
+                    args.random = Some(count);
+                }
+                "--profile" => {
+                    let runs = number(&arg, value(&arg)?)?;
+                    if runs == 0 {
+                        return Err("--profile needs at least one run".to_string());
+                    }
+                    args.profile = Some(runs);
+                }
+                "--seed" => {
+                    let seed = value(&arg)?;
+                    match seed.parse::<u64>() {
+                        Ok(seed) => args.seed = Some(seed),
+                        Err(_) => return Err(format!("--seed expects a number, got '{}'", seed)),
+                    }
+                }
+                "--trace" => args.trace = Some(PathBuf::from(value(&arg)?)),
+                "--normalize" => args.normalize = true,
+                "--jitter" => {
+                    let jitter = value(&arg)?;
+                    match jitter.parse::<f32>() {
+                        Ok(pixels) if pixels > 0.0 => args.jitter = Some(pixels),
+                        _ => {
+                            return Err(format!(
+                                "--jitter expects a positive number of pixels, got '{}'",
+                                jitter
+                            ));
+                        }
+                    }
+                }
+                "--jitter-seed" => {
+                    let seed = value(&arg)?;
+                    match seed.parse::<u64>() {
+                        Ok(seed) => args.jitter_seed = Some(seed),
+                        Err(_) => {
+                            return Err(format!("--jitter-seed expects a number, got '{}'", seed));
+                        }
+                    }
+                }
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                other => return Err(format!("Unknown argument '{}'\n\n{}", other, USAGE)),
+            }
+        }
+        if args.random.is_some() && (args.input.is_some() || args.recover) {
+            return Err("--random can't be combined with --input or --recover".to_string());
+        }
+        Ok(args)
+    }
+
+    // The invocation that would start the app with these arguments
+    pub fn to_command_line(&self) -> String {
+        let program = env::args().next().unwrap_or_else(|| "chaikin".to_string());
+        let mut parts = vec![quote(&program)];
+        if self.recover {
+            parts.push("--recover".to_string());
+        }
+        if let Some(input) = &self.input {
+            parts.push("--input".to_string());
+            parts.push(quote(&input.to_string_lossy()));
+        }
+        if let Some(iterations) = self.iterations {
+            parts.push("--iterations".to_string());
+            parts.push(iterations.to_string());
+        }
+        if let Some(max_points) = self.max_points {
+            parts.push("--max-points".to_string());
+            parts.push(max_points.to_string());
+        }
+        if self.closed {
+            parts.push("--closed".to_string());
+        }
+        if let Some(ratio) = self.ratio {
+            parts.push("--ratio".to_string());
+            parts.push(ratio.to_string());
+        }
+        if self.replay {
+            parts.push("--replay".to_string());
+        }
+        if self.save_on_exit {
+            parts.push("--save-on-exit".to_string());
+        }
+        if self.pressure_from_speed {
+            parts.push("--pressure-from-speed".to_string());
+        }
+        if let Some(random) = self.random {
+            parts.push("--random".to_string());
+            parts.push(random.to_string());
+        }
+        if let Some(seed) = self.seed {
+            parts.push("--seed".to_string());
+            parts.push(seed.to_string());
+        }
+        if let Some(runs) = self.profile {
+            parts.push("--profile".to_string());
+            parts.push(runs.to_string());
+        }
+        if let Some(trace) = &self.trace {
+            parts.push("--trace".to_string());
+            parts.push(quote(&trace.to_string_lossy()));
+        }
+        if self.normalize {
+            parts.push("--normalize".to_string());
+        }
+        if let Some(jitter) = self.jitter {
+            parts.push("--jitter".to_string());
+            parts.push(jitter.to_string());
+        }
+        if let Some(seed) = self.jitter_seed {
+            parts.push("--jitter-seed".to_string());
+            parts.push(seed.to_string());
+        }
+        parts.join(" ")
+    }
+}
+
+fn number(name: &str, value: String) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got '{}'", name, value))
+}
+
+fn quote(arg: &str) -> String {
+    if arg.is_empty() || arg.contains(|c: char| c.is_whitespace() || c == '\'' || c == '"') {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    } else {
+        arg.to_string()
+    }
+}
@@ -0,0 +1,17 @@
+use std::time::Instant;
+
+/// Source of the current time for the animation, so it can be driven by
+/// something other than the wall clock.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The real monotonic clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
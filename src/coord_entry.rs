@@ -0,0 +1,119 @@
+use minifb::Key;
+use nalgebra::Point2;
+
+use crate::draw::{Canvas, blend_point};
+use crate::text::{GLYPH_HEIGHT, draw_text, text_width};
+use crate::{HEIGHT, WIDTH};
+
+const PROMPT: &str = "Add point at x,y: ";
+// Longest input accepted, which also keeps the box from outgrowing the window
+const MAX_LENGTH: usize = 32;
+const PANEL_COLOR: u32 = 0xFF101018;
+const PANEL_ALPHA: f32 = 0.85;
+const PROMPT_COLOR: u32 = 0xFFCCCCCC;
+const INPUT_COLOR: u32 = 0xFFFFD060;
+const PADDING: i32 = 6;
+// The box starts this wide and grows if the input gets longer
+const MIN_INPUT_WIDTH: i32 = 80;
+// Distance from the bottom of the window, clear of the status line
+const BOTTOM_GAP: i32 = 40;
+
+// What a key press did to the entry
+pub enum EntryEvent {
+    // Enter with a valid "x,y"
+    Submit(Point2<f32>),
+    // Enter with something that isn't; the entry stays open to fix it
+    Invalid(String),
+    Cancel,
+}
+
+// A text box for typing the exact coordinates of a new point. While it is
+// open it takes every key press, so bindings don't fire while typing.
+pub struct CoordEntry {
+    text: String,
+}
+
+impl CoordEntry {
+    pub fn new() -> Self {
+        Self {
+            text: String::new(),
+        }
+    }
+
+    pub fn key(&mut self, key: Key) -> Option<EntryEvent> {
+        match key {
+            Key::Enter | Key::NumPadEnter => {
+                return Some(match parse_point(&self.text) {
+                    Ok(point) => EntryEvent::Submit(point),
+                    Err(message) => EntryEvent::Invalid(message),
+                });
+            }
+            Key::Escape => return Some(EntryEvent::Cancel),
+            Key::Backspace => {
+                self.text.pop();
+            }
+            _ => {
+                if let Some(c) = key_char(key)
+                    && self.text.len() < MAX_LENGTH
+                {
+                    self.text.push(c);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas) {
+        let input = format!("{}_", self.text);
+        let w = text_width(PROMPT) + text_width(&input).max(MIN_INPUT_WIDTH) + PADDING * 2;
+        let h = GLYPH_HEIGHT + PADDING * 2;
+        let left = (WIDTH as i32 - w) / 2;
+        let top = HEIGHT as i32 - BOTTOM_GAP - h;
+        for y in top..top + h {
+            for x in left..left + w {
+                blend_point(canvas, x, y, PANEL_COLOR, PANEL_ALPHA);
+            }
+        }
+
+        let x = left + PADDING;
+        draw_text(canvas, x, top + PADDING, PROMPT, PROMPT_COLOR);
+        draw_text(canvas, x + text_width(PROMPT), top + PADDING, &input, INPUT_COLOR);
+    }
+}
+
+// The character a key types into the entry, if any
+fn key_char(key: Key) -> Option<char> {
+    let c = match key {
+        Key::Key0 | Key::NumPad0 => '0',
+        Key::Key1 | Key::NumPad1 => '1',
+        Key::Key2 | Key::NumPad2 => '2',
+        Key::Key3 | Key::NumPad3 => '3',
+        Key::Key4 | Key::NumPad4 => '4',
+        Key::Key5 | Key::NumPad5 => '5',
+        Key::Key6 | Key::NumPad6 => '6',
+        Key::Key7 | Key::NumPad7 => '7',
+        Key::Key8 | Key::NumPad8 => '8',
+        Key::Key9 | Key::NumPad9 => '9',
+        Key::Minus | Key::NumPadMinus => '-',
+        Key::Period | Key::NumPadDot => '.',
+        Key::Comma => ',',
+        Key::Space => ' ',
+        _ => return None,
+    };
+    Some(c)
+}
+
+fn parse_point(text: &str) -> Result<Point2<f32>, String> {
+    let Some((x, y)) = text.split_once(',') else {
+        return Err(format!("Expected x,y, got '{}'", text.trim()));
+    };
+    let number = |value: &str| {
+        value
+            .trim()
+            .parse::<f32>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| format!("'{}' is not a number", value.trim()))
+    };
+    Ok(Point2::new(number(x)?, number(y)?))
+}
@@ -0,0 +1,1813 @@
+use nalgebra::{Point2, Rotation2, Vector2};
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+use crate::clock::{Clock, SystemClock};
+use crate::geometry::{
+    convex_hull, corner_angle, distance_to_segment, max_deviation, max_deviation_along,
+    point_in_polygon, simplify_indices,
+};
+
+pub const POINT_RADIUS: i32 = 5;
+pub const ANIMATION_STEP_DURATION: Duration = Duration::from_millis(500);
+pub const MAX_ANIMATION_STEPS: usize = 7;
+// Every step roughly doubles the point count, so never go deeper than this
+pub const STEP_LIMIT: usize = 12;
+pub const DEFAULT_RATIO: f32 = 0.25;
+// Delay between points when replaying the placement order
+pub const TRACE_STEP_DURATION: Duration = Duration::from_millis(200);
+// Most steps a late frame will advance by when catching up
+const MAX_CATCH_UP_STEPS: u32 = 4;
+// Points evaluated along each span of the limit curve
+const LIMIT_SAMPLES_PER_SPAN: usize = 16;
+// Samples per span when measuring a step's distance from the limit curve.
+// Odd, so they don't land on the middles of a level's edges, where every
+// level touches the limit and would measure as no distance at all.
+const ERROR_SAMPLES_PER_SPAN: usize = 101;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ControlPoint {
+    pub position: Point2<f32>,
+    pub selected: bool,
+    // Where the user actually clicked, when the point was snapped to a grid
+    pub raw_position: Option<Point2<f32>>,
+    // Locked points can still be selected but are never moved or deleted
+    pub locked: bool,
+    // Cusps stay exactly in place at every level, breaking the curve into
+    // pieces that are each smoothed on their own
+    pub cusp: bool,
+}
+
+impl ControlPoint {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            position: Point2::new(x, y),
+            selected: false,
+            raw_position: None,
+            locked: false,
+            cusp: false,
+        }
+    }
+}
+
+/// Line to reflect the control polygon across.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MirrorAxis {
+    /// The vertical line `x = c`.
+    Vertical(f32),
+    /// The horizontal line `y = c`.
+    Horizontal(f32),
+}
+
+// Smallest distance from the centroid to the farthest point that
+// `scale_all` will shrink down to
+const MIN_SCALE_EXTENT: f32 = POINT_RADIUS as f32 * 2.0;
+
+// Points this close to the mirror axis are their own reflection
+const MIRROR_EPSILON: f32 = 0.5;
+
+// `recenter` leaves the points alone until their centroid is this far from
+// the origin. f32 keeps about 7 significant digits, so out here positions
+// are still good to about a thousandth of a pixel; at 1e6 it is down to a
+// sixteenth.
+const RECENTER_DISTANCE: f32 = 10_000.0;
+
+/// Called with the index and points of every subdivision level as it is produced.
+pub type StepCallback = Box<dyn FnMut(usize, &[Point2<f32>])>;
+
+pub struct ChaikinCurve {
+    pub control_points: Vec<ControlPoint>,
+    pub animation_steps: Vec<Vec<Point2<f32>>>,
+    pub current_step: usize,
+    pub animating: bool,
+    pub last_step_time: Instant,
+    pub on_step: Option<StepCallback>,
+    // Where the animation reads the time; the system clock unless replaced
+    pub clock: Box<dyn Clock>,
+    // Number of subdivision levels generated on top of the control polygon
+    pub max_steps: usize,
+    // +1 plays from the control polygon towards the smoothest level, -1 back again
+    pub direction: i8,
+    // Wrap around at the ends of the animation instead of stopping there,
+    // both when it plays and when stepping by hand
+    pub looping: bool,
+    // When set, `add_point` refuses to grow the polygon past this many points
+    pub max_control_points: Option<usize>,
+    // While replaying the placement order, how many control points have been
+    // revealed so far; the smoothing animation starts once all are shown
+    pub revealed_points: Option<usize>,
+    // While building the curve up, how many of the control points (from the
+    // first) are smoothed so far
+    pub built_points: Option<usize>,
+    // Treat the polygon as a loop, joining the last point back to the first
+    pub closed: bool,
+    // Where each segment is cut, as a fraction of its length (0.25 is Chaikin's)
+    pub ratio: f32,
+    // Per-segment overrides of `ratio`, indexed by the segment starting at
+    // that control point (the last one closing the loop); `None`, or no
+    // entry at all, uses `ratio`
+    pub segment_ratios: Vec<Option<f32>>,
+    // Corners with an interior angle (in radians) below this are kept sharp
+    // instead of being cut
+    pub preserve_angle: Option<f32>,
+    // When set, subdivision stops early once a step moves the curve by less
+    // than this many pixels; `max_steps` is then only an upper bound
+    pub convergence_threshold: Option<f32>,
+    // When set, corners that turn by less than this (in radians) are left as
+    // they are instead of being cut, so straight stretches stay coarse and
+    // only the bends are refined
+    pub refine_angle: Option<f32>,
+    // Keep the animation on a wall-clock schedule: a late frame advances by
+    // every step that came due (up to `MAX_CATCH_UP_STEPS`) instead of one,
+    // and the next step is timed from when this one was due, not from now
+    pub catch_up: bool,
+    // While not animating, show the polygon already smoothed to `max_steps`
+    // instead of as straight segments, following every edit
+    pub live_preview: bool,
+    // Neighbouring control points closer together than this many pixels are
+    // smoothed as one (the first of them); 0 merges nothing. The points
+    // themselves are kept.
+    pub dedup_epsilon: f32,
+    // Write exported and saved points normalized into [0, 1] x [0, 1] by the
+    // control points' bounding box, which is stored alongside so they can
+    // be loaded back at their original size
+    pub normalize_exports: bool,
+    // When set, added and moved points are kept inside this rectangle, given
+    // by its top left and bottom right corners; points already outside it
+    // stay where they are until moved
+    pub keep_within: Option<(Point2<f32>, Point2<f32>)>,
+    // Each subdivision step nudges the points it makes by up to this many
+    // pixels along each axis, for a hand-drawn look; 0 leaves them exact. The
+    // nudges follow from `jitter_seed`, so regenerating gives the same curve
+    // until the seed changes.
+    pub jitter: f32,
+    pub jitter_seed: u64,
+    // Where the points of each generated step came from, so deeper levels
+    // keep their segment's ratio and their cusps
+    step_lineage: Vec<Lineage>,
+}
+
+// What one subdivision level carries over to the next
+#[derive(Debug, Clone, Default)]
+struct Lineage {
+    // The control polygon segment each segment was cut from
+    origins: Vec<usize>,
+    // Points that are never moved: the cusps, which split the curve
+    pinned: Vec<bool>,
+}
+
+impl ChaikinCurve {
+    pub fn new() -> Self {
+        Self {
+            control_points: Vec::new(),
+            animation_steps: Vec::new(),
+            current_step: 0,
+            animating: false,
+            last_step_time: Instant::now(),
+            on_step: None,
+            clock: Box::new(SystemClock),
+            max_steps: MAX_ANIMATION_STEPS,
+            direction: 1,
+            looping: true,
+            max_control_points: None,
+            revealed_points: None,
+            built_points: None,
+            closed: false,
+            ratio: DEFAULT_RATIO,
+            segment_ratios: Vec::new(),
+            preserve_angle: None,
+            convergence_threshold: None,
+            refine_angle: None,
+            catch_up: false,
+            live_preview: false,
+            dedup_epsilon: 0.0,
+            normalize_exports: false,
+            keep_within: None,
+            jitter: 0.0,
+            jitter_seed: 0,
+            step_lineage: Vec::new(),
+        }
+    }
+
+    /// Appends a control point, returning `false` if the point limit is
+    /// already reached.
+    pub fn add_point(&mut self, x: f32, y: f32) -> bool {
+        if self
+            .max_control_points
+            .is_some_and(|max| self.control_points.len() >= max)
+        {
+            return false;
+        }
+        let p = self.kept_within(Point2::new(x, y));
+        self.control_points.push(ControlPoint::new(p.x, p.y));
+        true
+    }
+
+    // `p` moved inside `keep_within`, if that is set
+    fn kept_within(&self, p: Point2<f32>) -> Point2<f32> {
+        match self.keep_within {
+            Some((min, max)) => p.sup(&min).inf(&max),
+            None => p,
+        }
+    }
+
+    /// Adds a point at the grid intersection nearest to (`x`, `y`),
+    /// remembering the unsnapped position.
+    pub fn add_point_snapped(&mut self, x: f32, y: f32, grid_size: f32) -> bool {
+        let snap = |v: f32| (v / grid_size).round() * grid_size;
+        if !self.add_point(snap(x), snap(y)) {
+            return false;
+        }
+        if let Some(point) = self.control_points.last_mut() {
+            point.raw_position = Some(Point2::new(x, y));
+        }
+        true
+    }
+
+    pub fn clear_points(&mut self) {
+        self.control_points.clear();
+        self.segment_ratios.clear();
+        self.reset_animation();
+    }
+
+    pub fn reset_animation(&mut self) {
+        self.animation_steps.clear();
+        self.step_lineage.clear();
+        self.current_step = 0;
+        self.animating = false;
+        self.revealed_points = None;
+        self.built_points = None;
+    }
+
+    /// Replays the order the points were placed in, one at a time, and then
+    /// runs the normal subdivision animation.
+    pub fn start_trace(&mut self) {
+        if self.control_points.is_empty() {
+            return;
+        }
+
+        self.reset_animation();
+        self.revealed_points = Some(1);
+        self.last_step_time = self.clock.now();
+    }
+
+    /// Shows the curve growing instead of sharpening: the first two control
+    /// points smoothed, then the first three, and so on, one more each step
+    /// until all of them are in. With `looping` it then starts over.
+    pub fn start_build_up(&mut self) {
+        if self.control_points.len() <= 1 {
+            return;
+        }
+
+        self.reset_animation();
+        self.built_points = Some(2);
+        self.last_step_time = self.clock.now();
+    }
+
+    // The first `count` control points smoothed to `max_steps` on their own,
+    // as if the rest weren't there yet
+    fn prefix_curve(&self, count: usize) -> Vec<Point2<f32>> {
+        let count = count.min(self.control_points.len());
+        let mut points: Vec<Point2<f32>> =
+            self.control_points[..count].iter().map(|cp| cp.position).collect();
+        let mut lineage = self.control_lineage(count);
+        for _ in 0..self.max_steps {
+            (points, lineage) = self.step_tracked(&points, &lineage, None);
+        }
+        points
+    }
+
+    /// The control points currently on display; during a trace only the
+    /// ones revealed so far.
+    pub fn visible_control_points(&self) -> &[ControlPoint] {
+        match self.revealed_points {
+            Some(count) => &self.control_points[..count.min(self.control_points.len())],
+            None => &self.control_points,
+        }
+    }
+
+    pub fn start_animation(&mut self) {
+        if self.control_points.len() <= 1 {
+            return; // Need at least 2 points to draw a curve
+        }
+
+        self.reset_animation();
+        self.generate_animation_steps();
+        if self.direction < 0 {
+            // Playing backwards starts from the smoothest level
+            self.current_step = self.animation_steps.len().saturating_sub(1);
+        }
+        self.animating = true;
+        self.last_step_time = self.clock.now();
+    }
+
+    pub fn generate_animation_steps(&mut self) {
+        self.animation_steps.clear();
+        self.step_lineage.clear();
+
+        // Initial step is just the control points
+        let (initial_points, mut current_lineage) = self.base_level();
+        self.notify_step(0, &initial_points);
+        self.animation_steps.push(initial_points.clone());
+        self.step_lineage.push(current_lineage.clone());
+
+        let mut current_points = initial_points;
+        
+        // Generate the subsequent steps
+        for step in 1..=self.max_steps {
+            let Some((next, lineage)) = self.next_level(&current_points, &current_lineage) else {
+                break;
+            };
+            current_points = next;
+            current_lineage = lineage;
+            self.notify_step(step, &current_points);
+            self.animation_steps.push(current_points.clone());
+            self.step_lineage.push(current_lineage.clone());
+        }
+
+        // An adaptive regeneration can come out shorter than the last one
+        self.current_step = self.current_step.min(self.animation_steps.len() - 1);
+    }
+
+    /// Raises `max_steps` by `additional`, subdividing only the new levels on
+    /// top of the ones already generated.
+    pub fn extend_steps(&mut self, additional: usize) {
+        let additional = additional.min(STEP_LIMIT.saturating_sub(self.max_steps));
+        self.max_steps += additional;
+
+        // Nothing generated yet; the next full generation picks up the new count
+        let Some(last) = self.animation_steps.last() else {
+            return;
+        };
+
+        let mut current_points = last.clone();
+        let mut current_lineage = match self.step_lineage.last() {
+            Some(lineage) => lineage.clone(),
+            None => self.control_lineage(current_points.len()),
+        };
+        for _ in 0..additional {
+            let step = self.animation_steps.len();
+            let Some((next, lineage)) = self.next_level(&current_points, &current_lineage) else {
+                break;
+            };
+            current_points = next;
+            current_lineage = lineage;
+            self.notify_step(step, &current_points);
+            self.animation_steps.push(current_points.clone());
+            self.step_lineage.push(current_lineage.clone());
+        }
+    }
+
+    /// Changes the number of subdivision levels, extending or truncating the
+    /// generated steps rather than regenerating them.
+    pub fn set_max_steps(&mut self, steps: usize) {
+        if steps > self.max_steps {
+            self.extend_steps(steps - self.max_steps);
+        } else {
+            self.max_steps = steps;
+            self.animation_steps.truncate(steps + 1);
+            self.step_lineage.truncate(steps + 1);
+            self.current_step = self.current_step.min(steps);
+        }
+    }
+
+    // The next subdivision level, or `None` if it would have converged and
+    // barely differ from `points`
+    fn next_level(
+        &self,
+        points: &[Point2<f32>],
+        lineage: &Lineage,
+    ) -> Option<(Vec<Point2<f32>>, Lineage)> {
+        let (next, next_lineage) = self.step_tracked(points, lineage, None);
+        let converged = self
+            .convergence_threshold
+            .is_some_and(|threshold| max_deviation(points, &next) < threshold);
+        (!converged).then_some((next, next_lineage))
+    }
+
+    fn notify_step(&mut self, step: usize, points: &[Point2<f32>]) {
+        if let Some(on_step) = self.on_step.as_mut() {
+            on_step(step, points);
+        }
+    }
+
+    /// One subdivision pass treating `points` as the control polygon, so
+    /// segment `i` is cut at its entry in `segment_ratios` and point `i` is
+    /// pinned if control point `i` is a cusp.
+    pub fn chaikin_step(&self, points: &[Point2<f32>]) -> Vec<Point2<f32>> {
+        self.step_tracked(points, &self.control_lineage(points.len()), None).0
+    }
+
+    /// One subdivision pass cutting each segment at `ratio` and `1 - ratio`
+    /// of its length.
+    pub fn chaikin_step_with_ratio(&self, points: &[Point2<f32>], ratio: f32) -> Vec<Point2<f32>> {
+        self.subdivide(points, &[], |_| ratio).0
+    }
+
+    /// The ratio segment `segment` of the control polygon is cut at.
+    pub fn segment_ratio(&self, segment: usize) -> f32 {
+        self.segment_ratios.get(segment).copied().flatten().unwrap_or(self.ratio)
+    }
+
+    /// Overrides the ratio of one control polygon segment.
+    pub fn set_segment_ratio(&mut self, segment: usize, ratio: f32) {
+        if self.segment_ratios.len() <= segment {
+            self.segment_ratios.resize(segment + 1, None);
+        }
+        self.segment_ratios[segment] = Some(ratio);
+    }
+
+    /// Index of the control polygon segment closest to (`x`, `y`).
+    pub fn nearest_segment(&self, x: f32, y: f32) -> Option<usize> {
+        let p = Point2::new(x, y);
+        let points = &self.control_points;
+        let count = if self.is_closed() { points.len() } else { points.len().saturating_sub(1) };
+        (0..count)
+            .map(|i| {
+                let (a, b) = (points[i].position, points[(i + 1) % points.len()].position);
+                (i, distance_to_segment(p, a, b))
+            })
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(i, _)| i)
+    }
+
+    // `segment_ratios` with an entry for every control point, so the ratios
+    // can be moved and dropped along with the points: segment `i` is the one
+    // starting at point `i`
+    fn ratios_per_point(&self) -> Vec<Option<f32>> {
+        let mut ratios = self.segment_ratios.clone();
+        ratios.resize(self.control_points.len().max(ratios.len()), None);
+        ratios
+    }
+
+    // Segments in a level of `len` points
+    fn segment_count(&self, len: usize) -> usize {
+        if self.closed { len } else { len.saturating_sub(1) }
+    }
+
+    // Lineage of the control polygon itself: each segment is its own origin
+    // and the cusps are pinned
+    fn control_lineage(&self, len: usize) -> Lineage {
+        Lineage {
+            origins: (0..self.segment_count(len)).collect(),
+            pinned: (0..len)
+                .map(|i| self.control_points.get(i).is_some_and(|cp| cp.cusp))
+                .collect(),
+        }
+    }
+
+    // The control polygon as subdivision starts from it, with the points that
+    // are within `dedup_epsilon` of the previous one merged into it. A merged
+    // run keeps the ratio of its first segment and is a cusp if any of its
+    // points is.
+    fn base_level(&self) -> (Vec<Point2<f32>>, Lineage) {
+        let mut points: Vec<Point2<f32>> = Vec::new();
+        let mut lineage = Lineage::default();
+        for (i, cp) in self.control_points.iter().enumerate() {
+            let duplicate = points
+                .last()
+                .is_some_and(|last| (cp.position - last).norm() < self.dedup_epsilon);
+            if duplicate {
+                if let Some(pinned) = lineage.pinned.last_mut() {
+                    *pinned |= cp.cusp;
+                }
+                continue;
+            }
+            points.push(cp.position);
+            lineage.origins.push(i);
+            lineage.pinned.push(cp.cusp);
+        }
+
+        // Closing the loop can bring the last point back onto the first
+        if self.closed
+            && points.len() > 2
+            && (points[points.len() - 1] - points[0]).norm() < self.dedup_epsilon
+        {
+            points.pop();
+            lineage.origins.pop();
+            if let Some(pinned) = lineage.pinned.pop() {
+                lineage.pinned[0] |= pinned;
+            }
+        }
+        lineage.origins.truncate(self.segment_count(points.len()));
+        (points, lineage)
+    }
+
+    // One pass cutting each segment at the ratio of the control segment it
+    // came from (or at `ratio` for all of them), along with the new level's
+    // lineage
+    fn step_tracked(
+        &self,
+        points: &[Point2<f32>],
+        lineage: &Lineage,
+        ratio: Option<f32>,
+    ) -> (Vec<Point2<f32>>, Lineage) {
+        let origin = |segment: usize| lineage.origins.get(segment).copied().unwrap_or(segment);
+        let (next, sources, pinned) = self.subdivide(points, &lineage.pinned, |segment| {
+            ratio.unwrap_or_else(|| self.segment_ratio(origin(segment)))
+        });
+        let origins = sources
+            .iter()
+            .take(self.segment_count(next.len()))
+            .map(|&segment| origin(segment))
+            .collect();
+        let next = self.jittered(next, &pinned);
+        (next, Lineage { origins, pinned })
+    }
+
+    // `points` with the jitter applied, leaving the pinned points and the
+    // ends of an open curve where they are
+    fn jittered(&self, mut points: Vec<Point2<f32>>, pinned: &[bool]) -> Vec<Point2<f32>> {
+        if self.jitter <= 0.0 {
+            return points;
+        }
+        let n = points.len();
+        for (i, point) in points.iter_mut().enumerate() {
+            let fixed = pinned.get(i).copied().unwrap_or(false)
+                || (!self.closed && (i == 0 || i + 1 == n));
+            if !fixed {
+                // Keyed by the level's size too, so each level gets its own nudges
+                let key = mix(self.jitter_seed) ^ ((n as u64) << 32) ^ i as u64;
+                // The top 24 bits of each hash, spread over -1..1
+                let [x, y] = [mix(key), mix(!key)]
+                    .map(|bits| (bits >> 40) as f32 / (1 << 23) as f32 - 1.0);
+                *point += Vector2::new(x, y) * self.jitter;
+            }
+        }
+        points
+    }
+
+    // The subdivision itself, cutting segment `i` at `ratio_of(i)`. Points
+    // marked in `pinned` stay where they are, and the curve either side of
+    // one is smoothed as if it ended there. Also returns, for each new
+    // point, the segment it was cut from (a new segment belongs to the same
+    // one as the point it starts at) and whether it is pinned.
+    fn subdivide(
+        &self,
+        points: &[Point2<f32>],
+        pinned: &[bool],
+        ratio_of: impl Fn(usize) -> f32,
+    ) -> (Vec<Point2<f32>>, Vec<usize>, Vec<bool>) {
+        let n = points.len();
+        // The ends of an open curve are kept anyway, so only inner points count
+        let is_pinned = |i: usize| {
+            pinned.get(i).copied().unwrap_or(false) && (self.closed || (i > 0 && i + 1 < n))
+        };
+        if n <= 2 {
+            let last = self.segment_count(n).saturating_sub(1);
+            let sources = (0..n).map(|i| i.min(last)).collect();
+            return (points.to_vec(), sources, (0..n).map(is_pinned).collect());
+        }
+
+        let cut = |p0: Point2<f32>, p1: Point2<f32>, ratio: f32| {
+            // Calculate the cut points, 1/4 and 3/4 along the segment for
+            // the classic Chaikin ratio
+            // Instead of adding points directly, we interpolate between them
+            let q = Point2::new(
+                p0.x * (1.0 - ratio) + p1.x * ratio,
+                p0.y * (1.0 - ratio) + p1.y * ratio
+            );
+            
+            let r = Point2::new(
+                p0.x * ratio + p1.x * (1.0 - ratio),
+                p0.y * ratio + p1.y * (1.0 - ratio)
+            );
+
+            [q, r]
+        };
+
+        let mut result = Vec::new();
+        let mut sources = Vec::new();
+        let mut pinned_out = Vec::new();
+        let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+            return (result, sources, pinned_out);
+        };
+
+        // A preserved corner, or one too flat to refine, takes the place of
+        // the two cuts either side of it. A pinned point instead keeps both
+        // cuts and sits between them.
+        let sharp: Vec<bool> = (0..n)
+            .map(|i| {
+                !is_pinned(i)
+                    && (self.is_sharp_corner(points, i) || self.is_flat_corner(points, i))
+            })
+            .collect();
+        let mut cut_segment = |result: &mut Vec<Point2<f32>>, i: usize, j: usize| {
+            let [q, r] = cut(points[i], points[j], ratio_of(i));
+            result.push(if sharp[i] { points[i] } else { q });
+            sources.push(i);
+            pinned_out.push(false);
+            if !sharp[j] {
+                result.push(r);
+                sources.push(i);
+                pinned_out.push(false);
+            }
+            if is_pinned(j) {
+                result.push(points[j]);
+                sources.push(j);
+                pinned_out.push(true);
+            }
+        };
+
+        // For open curves, keep the first and last points. Closed curves also
+        // cut the segment from the last point back to the first.
+        if !self.closed {
+            result.push(first);
+        }
+
+        for i in 1..n {
+            cut_segment(&mut result, i - 1, i);
+        }
+
+        if self.closed {
+            cut_segment(&mut result, n - 1, 0);
+        } else {
+            result.push(last);
+        }
+
+        if !self.closed {
+            sources.insert(0, 0);
+            sources.push(n - 2);
+            pinned_out.insert(0, false);
+            pinned_out.push(false);
+        }
+        (result, sources, pinned_out)
+    }
+
+    // Whether `points[index]` is a corner sharp enough to survive smoothing.
+    // The ends of an open curve are always kept anyway, so they never count.
+    fn is_sharp_corner(&self, points: &[Point2<f32>], index: usize) -> bool {
+        let Some(threshold) = self.preserve_angle else {
+            return false;
+        };
+        let n = points.len();
+        if !self.closed && (index == 0 || index + 1 == n) {
+            return false;
+        }
+
+        let prev = points[(index + n - 1) % n];
+        let next = points[(index + 1) % n];
+        corner_angle(prev, points[index], next).is_some_and(|angle| angle < threshold)
+    }
+
+    // Whether `points[index]` turns too little to be worth refining. Like
+    // sharp corners, the ends of an open curve never count.
+    fn is_flat_corner(&self, points: &[Point2<f32>], index: usize) -> bool {
+        let Some(threshold) = self.refine_angle else {
+            return false;
+        };
+        let n = points.len();
+        if !self.closed && (index == 0 || index + 1 == n) {
+            return false;
+        }
+
+        let prev = points[(index + n - 1) % n];
+        let next = points[(index + 1) % n];
+        corner_angle(prev, points[index], next).is_some_and(|angle| PI - angle < threshold)
+    }
+
+    fn since_last_step(&self) -> Duration {
+        self.clock.now().saturating_duration_since(self.last_step_time)
+    }
+
+    pub fn update_animation(&mut self) {
+        if let Some(count) = self.revealed_points {
+            if self.since_last_step() >= TRACE_STEP_DURATION {
+                if count >= self.control_points.len() {
+                    self.revealed_points = None;
+                    self.start_animation();
+                } else {
+                    self.revealed_points = Some(count + 1);
+                    self.last_step_time = self.clock.now();
+                }
+            }
+            return;
+        }
+
+        if let Some(count) = self.built_points {
+            if self.since_last_step() >= ANIMATION_STEP_DURATION {
+                if count < self.control_points.len() {
+                    self.built_points = Some(count + 1);
+                } else if self.looping {
+                    self.built_points = Some(2);
+                }
+                self.last_step_time = self.clock.now();
+            }
+            return;
+        }
+
+        if !self.animating || self.animation_steps.is_empty() {
+            return;
+        }
+
+        let elapsed = self.since_last_step();
+        if elapsed < ANIMATION_STEP_DURATION {
+            return;
+        }
+
+        if !self.catch_up {
+            self.advance_step();
+            self.last_step_time = self.clock.now();
+            return;
+        }
+
+        let due = (elapsed.as_nanos() / ANIMATION_STEP_DURATION.as_nanos()) as u32;
+        for _ in 0..due.min(MAX_CATCH_UP_STEPS) {
+            self.advance_step();
+        }
+        if due > MAX_CATCH_UP_STEPS {
+            // Too far behind (a stall, or the window was dragged); start the
+            // schedule afresh rather than racing through the backlog
+            self.last_step_time = self.clock.now();
+        } else {
+            self.last_step_time += ANIMATION_STEP_DURATION * due;
+        }
+    }
+
+    // Moves `current_step` one level in `direction`, wrapping or clamping at
+    // the ends depending on `looping`
+    fn advance_step(&mut self) {
+        self.move_step(self.direction as isize);
+    }
+
+    fn move_step(&mut self, by: isize) {
+        let last = self.animation_steps.len() as isize - 1;
+        let next = self.current_step as isize + by;
+        self.current_step = if self.looping {
+            next.rem_euclid(last + 1) as usize
+        } else {
+            next.clamp(0, last) as usize
+        };
+    }
+
+    /// How far through the wait for the next step the animation is, from 0 to
+    /// 1, or `None` when nothing is going to advance
+    pub fn step_progress(&self) -> Option<f32> {
+        let duration = if self.revealed_points.is_some() {
+            TRACE_STEP_DURATION
+        } else if let Some(count) = self.built_points {
+            if count >= self.control_points.len() && !self.looping {
+                return None;
+            }
+            ANIMATION_STEP_DURATION
+        } else {
+            if !self.animating || self.animation_steps.is_empty() || self.is_finished() {
+                return None;
+            }
+            ANIMATION_STEP_DURATION
+        };
+        Some((self.since_last_step().as_secs_f32() / duration.as_secs_f32()).min(1.0))
+    }
+
+    /// Whether a non-looping animation has stopped at the end it was playing
+    /// towards: the smoothest level, or the control polygon when reversed.
+    pub fn is_finished(&self) -> bool {
+        if self.looping || !self.animating || self.revealed_points.is_some() {
+            return false;
+        }
+        let Some(last) = self.animation_steps.len().checked_sub(1) else {
+            return false;
+        };
+        if self.direction > 0 {
+            self.current_step >= last
+        } else {
+            self.current_step == 0
+        }
+    }
+
+    /// Shows the next subdivision level, starting the animation first if it
+    /// isn't running. Past the last level it wraps or stops like the
+    /// animation does, and the next automatic step waits a full step from now.
+    pub fn step_forward(&mut self) {
+        self.step_by(1);
+    }
+
+    /// Like `step_forward`, towards the control polygon.
+    pub fn step_back(&mut self) {
+        self.step_by(-1);
+    }
+
+    fn step_by(&mut self, by: isize) {
+        if !self.animating || self.revealed_points.is_some() {
+            self.revealed_points = None;
+            self.start_animation();
+            return;
+        }
+        if !self.animation_steps.is_empty() {
+            self.move_step(by);
+            self.last_step_time = self.clock.now();
+        }
+    }
+
+    pub fn reverse_direction(&mut self) {
+        self.direction = -self.direction;
+    }
+
+    pub fn point_index_at(&self, x: f32, y: f32) -> Option<usize> {
+        self.control_points.iter().position(|point| {
+            let dx = point.position.x - x;
+            let dy = point.position.y - y;
+            dx * dx + dy * dy <= (POINT_RADIUS as f32 * POINT_RADIUS as f32)
+        })
+    }
+
+    pub fn select_point_at(&mut self, x: f32, y: f32) -> bool {
+        if let Some(index) = self.point_index_at(x, y) {
+            self.control_points[index].selected = true;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Flips the selection of the point under the cursor, for building up a
+    /// multi-point selection.
+    pub fn toggle_selection_at(&mut self, x: f32, y: f32) -> bool {
+        if let Some(index) = self.point_index_at(x, y) {
+            let point = &mut self.control_points[index];
+            point.selected = !point.selected;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Pins or unpins the point under the cursor.
+    pub fn toggle_lock_at(&mut self, x: f32, y: f32) -> bool {
+        if let Some(index) = self.point_index_at(x, y) {
+            let point = &mut self.control_points[index];
+            point.locked = !point.locked;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Makes the point under the cursor a cusp, or a smooth point again.
+    pub fn toggle_cusp_at(&mut self, x: f32, y: f32) -> bool {
+        let Some(index) = self.point_index_at(x, y) else {
+            return false;
+        };
+        let point = &mut self.control_points[index];
+        point.cusp = !point.cusp;
+        if self.animating {
+            self.generate_animation_steps();
+        }
+        true
+    }
+
+    pub fn is_point_selected_at(&self, x: f32, y: f32) -> bool {
+        self.point_index_at(x, y)
+            .is_some_and(|index| self.control_points[index].selected)
+    }
+
+    pub fn deselect_all_points(&mut self) {
+        for point in &mut self.control_points {
+            point.selected = false;
+        }
+    }
+
+    /// Moves every selected point by the same offset, so a multi-point
+    /// selection keeps its shape while being dragged.
+    pub fn translate_selected_points(&mut self, dx: f32, dy: f32) {
+        for point in &mut self.control_points {
+            if point.selected && !point.locked {
+                let moved = point.position + Vector2::new(dx, dy);
+                point.position = match self.keep_within {
+                    // Pressed against the edge, a point slides along it
+                    Some((min, max)) => moved.sup(&min).inf(&max),
+                    None => moved,
+                };
+                // Once moved, the original click no longer explains the position
+                point.raw_position = None;
+            }
+        }
+    }
+
+    /// Moves the control point at `from` to index `to`, shifting the points
+    /// in between, so the curve visits it at a different place in its order.
+    pub fn move_point(&mut self, from: usize, to: usize) {
+        let len = self.control_points.len();
+        if from >= len || to >= len || from == to {
+            return;
+        }
+        // The segment leaving the point goes with it
+        let mut ratios = self.ratios_per_point();
+        let ratio = ratios.remove(from);
+        ratios.insert(to, ratio);
+        self.segment_ratios = ratios;
+        let point = self.control_points.remove(from);
+        self.control_points.insert(to, point);
+        if self.animating {
+            self.generate_animation_steps();
+        }
+    }
+
+    pub fn delete_selected(&mut self) {
+        let keep: Vec<bool> =
+            self.control_points.iter().map(|point| !point.selected || point.locked).collect();
+        if keep.iter().all(|&kept| kept) {
+            return;
+        }
+        // A deleted point takes the segment leaving it along, so the one
+        // arriving at it keeps its ratio and now reaches the next point
+        self.segment_ratios = self
+            .ratios_per_point()
+            .into_iter()
+            .zip(&keep)
+            .filter_map(|(ratio, &kept)| kept.then_some(ratio))
+            .collect();
+        let mut kept = keep.into_iter();
+        self.control_points.retain(|_| kept.next().unwrap_or(true));
+
+        if self.animating && self.control_points.len() > 1 {
+            self.generate_animation_steps();
+            let last_step = self.animation_steps.len().saturating_sub(1);
+            self.current_step = self.current_step.min(last_step);
+        } else {
+            self.reset_animation();
+        }
+    }
+
+    /// Appends a reflected copy of the control polygon. The copy is reversed
+    /// so the traversal runs out along the original and back along the
+    /// reflection, and points on the axis aren't duplicated. Returns `false`
+    /// (leaving the curve untouched) if the point limit would be exceeded.
+    pub fn mirror_across(&mut self, axis: MirrorAxis) -> bool {
+        let reflected: Vec<ControlPoint> = self
+            .control_points
+            .iter()
+            .rev()
+            .filter_map(|cp| {
+                let (x, y) = (cp.position.x, cp.position.y);
+                let (mirrored_x, mirrored_y, distance) = match axis {
+                    MirrorAxis::Vertical(c) => (2.0 * c - x, y, (x - c).abs()),
+                    MirrorAxis::Horizontal(c) => (x, 2.0 * c - y, (y - c).abs()),
+                };
+                (distance > MIRROR_EPSILON).then(|| ControlPoint::new(mirrored_x, mirrored_y))
+            })
+            .collect();
+
+        let total = self.control_points.len() + reflected.len();
+        if self.max_control_points.is_some_and(|max| total > max) {
+            return false;
+        }
+
+        self.control_points.extend(reflected);
+        if self.animating {
+            self.generate_animation_steps();
+        }
+        true
+    }
+
+    /// Rotates every control point by `radians` around their centroid.
+    pub fn rotate_all(&mut self, radians: f32) {
+        let Some(centroid) = self.centroid() else {
+            return;
+        };
+
+        let rotation = Rotation2::new(radians);
+        for point in &mut self.control_points {
+            point.position = centroid + rotation * (point.position - centroid);
+        }
+
+        if self.animating {
+            self.generate_animation_steps();
+        }
+    }
+
+    /// Scales every control point by `factor` around their centroid. Shrinking
+    /// stops once the points would all sit within a few pixels of each other,
+    /// so the shape can still be scaled back up. Returns whether it scaled.
+    pub fn scale_all(&mut self, factor: f32) -> bool {
+        let Some(centroid) = self.centroid() else {
+            return false;
+        };
+
+        let extent = self
+            .control_points
+            .iter()
+            .map(|cp| (cp.position - centroid).norm())
+            .fold(0.0, f32::max);
+        if factor < 1.0 && extent * factor < MIN_SCALE_EXTENT {
+            return false;
+        }
+
+        for point in &mut self.control_points {
+            point.position = centroid + (point.position - centroid) * factor;
+        }
+
+        if self.animating {
+            self.generate_animation_steps();
+        }
+        true
+    }
+
+    /// Once the points have drifted far from the origin, where `f32` loses
+    /// precision, moves them all (with the generated steps) back towards it
+    /// by a whole multiple of `granularity`, so a grid of that size still
+    /// lines up. Returns the shift that was subtracted, for the view to
+    /// follow so nothing moves on screen.
+    pub fn recenter(&mut self, granularity: f32) -> Option<Vector2<f32>> {
+        let centroid = self.centroid()?;
+        if centroid.coords.norm() < RECENTER_DISTANCE {
+            return None;
+        }
+
+        let shift = (centroid.coords / granularity).map(f32::round) * granularity;
+        for point in &mut self.control_points {
+            point.position -= shift;
+            if let Some(raw) = point.raw_position.as_mut() {
+                *raw -= shift;
+            }
+        }
+        for point in self.animation_steps.iter_mut().flatten() {
+            *point -= shift;
+        }
+        Some(shift)
+    }
+
+    /// The average position of the control points, which `rotate_all` and
+    /// `scale_all` transform around; `None` when there are none.
+    pub fn centroid(&self) -> Option<Point2<f32>> {
+        if self.control_points.is_empty() {
+            return None;
+        }
+        let sum = self
+            .control_points
+            .iter()
+            .fold(Point2::origin().coords, |acc, cp| acc + cp.position.coords);
+        Some(Point2::from(sum / self.control_points.len() as f32))
+    }
+
+    /// Corners of the convex hull of the control points. The smoothed curve
+    /// never leaves it.
+    pub fn convex_hull(&self) -> Vec<Point2<f32>> {
+        let positions: Vec<Point2<f32>> =
+            self.control_points.iter().map(|cp| cp.position).collect();
+        convex_hull(&positions)
+    }
+
+    /// Drops control points that lie within `epsilon` of the simplified
+    /// polyline (Ramer–Douglas–Peucker), keeping both endpoints.
+    pub fn simplify(&mut self, epsilon: f32) {
+        self.simplify_from(0, epsilon);
+    }
+
+    /// Like `simplify`, but leaves the points before `start` alone, for
+    /// tidying up just the most recently added run of points.
+    pub fn simplify_from(&mut self, start: usize, epsilon: f32) {
+        let start = start.min(self.control_points.len());
+        let positions: Vec<Point2<f32>> =
+            self.control_points[start..].iter().map(|cp| cp.position).collect();
+        let keep = simplify_indices(&positions, epsilon);
+        if keep.len() == positions.len() {
+            return;
+        }
+
+        // A merged run of segments keeps the ratio of the one it starts with
+        let ratios: Vec<Option<f32>> = keep
+            .iter()
+            .map(|&i| self.segment_ratios.get(start + i).copied().flatten())
+            .collect();
+        if self.segment_ratios.len() > start {
+            self.segment_ratios.truncate(start);
+            self.segment_ratios.extend(ratios);
+        }
+
+        let kept: Vec<ControlPoint> =
+            keep.into_iter().map(|i| self.control_points[start + i]).collect();
+        self.control_points.truncate(start);
+        self.control_points.extend(kept);
+        if self.animating {
+            self.generate_animation_steps();
+        }
+    }
+
+    /// Whether the curve is drawn and smoothed as a loop. Needs at least
+    /// three points; fewer are always treated as an open curve.
+    pub fn is_closed(&self) -> bool {
+        self.closed && self.control_points.len() >= 3
+    }
+
+    /// Switches between an open curve and a closed loop. Only the smoothing
+    /// changes: the control points are left exactly as they are, and a
+    /// running animation is regenerated from them.
+    pub fn toggle_closed(&mut self) {
+        self.closed = !self.closed;
+        if self.animating {
+            self.generate_animation_steps();
+        }
+    }
+
+    /// Even-odd test of whether (`x`, `y`) lies inside the smoothed closed
+    /// curve. Always `false` for open curves.
+    pub fn contains_point(&self, x: f32, y: f32) -> bool {
+        if !self.is_closed() {
+            return false;
+        }
+        point_in_polygon(Point2::new(x, y), &self.final_curve())
+    }
+
+    /// The fully subdivided curve at `max_steps`, whether or not an
+    /// animation is running.
+    pub fn final_curve(&self) -> Vec<Point2<f32>> {
+        self.subdivision_levels(None).pop().unwrap_or_default()
+    }
+
+    // The control polygon followed by all `max_steps` levels, computed from
+    // scratch rather than read from the animation. `ratio` overrides the
+    // ratio of every segment.
+    pub(crate) fn subdivision_levels(&self, ratio: Option<f32>) -> Vec<Vec<Point2<f32>>> {
+        let (mut points, mut lineage) = self.base_level();
+        let mut levels = vec![points.clone()];
+        for _ in 0..self.max_steps {
+            (points, lineage) = self.step_tracked(&points, &lineage, ratio);
+            levels.push(points.clone());
+        }
+        levels
+    }
+
+    /// The curve plain Chaikin subdivision converges to after infinitely many
+    /// steps: the quadratic B-spline of the control polygon, evaluated
+    /// directly. An open curve's ends are doubled, as keeping them each step
+    /// amounts to, so it runs straight from each end to its segment's middle.
+    /// Ratios other than 0.25, kept corners and cusps are not taken into
+    /// account.
+    pub fn limit_curve(&self) -> Vec<Point2<f32>> {
+        self.limit_samples(LIMIT_SAMPLES_PER_SPAN)
+    }
+
+    // `limit_curve` evaluated `per_span` times along each span
+    fn limit_samples(&self, per_span: usize) -> Vec<Point2<f32>> {
+        let points: Vec<Point2<f32>> = self.control_points.iter().map(|cp| cp.position).collect();
+        let n = points.len();
+        if n < 2 || (self.closed && n <= 2) {
+            return points;
+        }
+
+        // Each span is shaped by three consecutive control points
+        let spans: Vec<[Point2<f32>; 3]> = if self.closed {
+            (0..n)
+                .map(|i| [points[(i + n - 1) % n], points[i], points[(i + 1) % n]])
+                .collect()
+        } else {
+            let mut padded = vec![points[0]];
+            padded.extend_from_slice(&points);
+            padded.push(points[n - 1]);
+            padded.windows(3).map(|w| [w[0], w[1], w[2]]).collect()
+        };
+
+        let mut curve = Vec::with_capacity(spans.len() * per_span + 1);
+        for [a, b, c] in &spans {
+            for sample in 0..per_span {
+                let t = sample as f32 / per_span as f32;
+                let (wa, wc) = (0.5 * (1.0 - t) * (1.0 - t), 0.5 * t * t);
+                curve.push(Point2::from(
+                    a.coords * wa + b.coords * (1.0 - wa - wc) + c.coords * wc,
+                ));
+            }
+        }
+        // Where the last span ends: the start again when closed
+        let [_, b, c] = spans[spans.len() - 1];
+        curve.push(Point2::from(b.coords.lerp(&c.coords, 0.5)));
+        curve
+    }
+
+    /// How far subdivision level `step` (0 being the control polygon) still
+    /// is from the limit curve: the largest distance from a sample of the
+    /// limit curve to the level's polyline. Read from the animation when
+    /// one has been generated; a `step` past the last level measures the
+    /// last one.
+    pub fn step_error(&self, step: usize) -> f32 {
+        let computed;
+        let levels = if self.animation_steps.is_empty() {
+            computed = self.subdivision_levels(None);
+            &computed
+        } else {
+            &self.animation_steps
+        };
+        let Some(level) = levels.get(step).or(levels.last()) else {
+            return 0.0;
+        };
+        let samples = self.limit_samples(ERROR_SAMPLES_PER_SPAN);
+        max_deviation_along(&samples, level, self.is_closed())
+    }
+
+    /// Like `final_curve`, but cutting the segments at `ratio` instead.
+    pub fn final_curve_with_ratio(&self, ratio: f32) -> Vec<Point2<f32>> {
+        self.subdivision_levels(Some(ratio)).pop().unwrap_or_default()
+    }
+
+    pub fn get_current_points(&self) -> Vec<Point2<f32>> {
+        if self.revealed_points.is_some() {
+            // Mid-trace: the polygon grows one point at a time
+            self.visible_control_points().iter().map(|cp| cp.position).collect()
+        } else if let Some(count) = self.built_points {
+            self.prefix_curve(count)
+        } else if self.animating && !self.animation_steps.is_empty() {
+            // Return the current step of the animation
+            self.animation_steps[self.current_step].clone()
+        } else if self.control_points.len() >= 2 && self.live_preview {
+            self.final_curve()
+        } else if self.control_points.len() >= 2 {
+            // Return just the control points when not animating
+            self.control_points.iter().map(|cp| cp.position).collect()
+        } else if self.control_points.len() == 1 {
+            // Return the single point
+            vec![self.control_points[0].position]
+        } else {
+            // No points at all
+            vec![]
+        }
+    }
+}
+
+impl Default for ChaikinCurve {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Every level of plain Chaikin smoothing of the open polyline `points`, from
+/// the points themselves (level 0) to `iterations` passes.
+pub fn chaikin_all_levels(points: &[Point2<f32>], iterations: usize) -> Vec<Vec<Point2<f32>>> {
+    let mut levels = vec![points.to_vec()];
+    for _ in 0..iterations {
+        let next = plain_step(&levels[levels.len() - 1]);
+        levels.push(next);
+    }
+    levels
+}
+
+/// Only the given level of `chaikin_all_levels`, without keeping the others.
+pub fn chaikin_level(points: &[Point2<f32>], level: usize) -> Vec<Point2<f32>> {
+    let mut current = points.to_vec();
+    for _ in 0..level {
+        current = plain_step(&current);
+    }
+    current
+}
+
+/// The smoothed curve after `iterations` passes, the last of
+/// `chaikin_all_levels`.
+pub fn chaikin_final(points: &[Point2<f32>], iterations: usize) -> Vec<Point2<f32>> {
+    chaikin_level(points, iterations)
+}
+
+// One pass of plain Chaikin smoothing of an open polyline: both ends stay
+// and every segment is cut a quarter of the way in from each end, as a new
+// `ChaikinCurve` would. Two or fewer points are left as they are.
+fn plain_step(points: &[Point2<f32>]) -> Vec<Point2<f32>> {
+    if points.len() <= 2 {
+        return points.to_vec();
+    }
+    let ratio = DEFAULT_RATIO;
+    let mut result = vec![points[0]];
+    for segment in points.windows(2) {
+        let (p0, p1) = (segment[0], segment[1]);
+        result.push(Point2::new(
+            p0.x * (1.0 - ratio) + p1.x * ratio,
+            p0.y * (1.0 - ratio) + p1.y * ratio,
+        ));
+        result.push(Point2::new(
+            p0.x * ratio + p1.x * (1.0 - ratio),
+            p0.y * ratio + p1.y * (1.0 - ratio),
+        ));
+    }
+    result.push(points[points.len() - 1]);
+    result
+}
+
+// SplitMix64's finaliser, scrambling `key` into well spread bits
+fn mix(key: u64) -> u64 {
+    let mut z = key.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::rc::Rc;
+
+    use super::*;
+
+    fn curve(points: &[(f32, f32)]) -> ChaikinCurve {
+        let mut curve = ChaikinCurve::new();
+        for &(x, y) in points {
+            curve.add_point(x, y);
+        }
+        curve
+    }
+
+    // A clock that only moves when told to, shared with the curve it drives
+    #[derive(Clone)]
+    struct ManualClock(Rc<Cell<Instant>>);
+
+    impl ManualClock {
+        fn new() -> Self {
+            Self(Rc::new(Cell::new(Instant::now())))
+        }
+
+        fn advance(&self, by: Duration) {
+            self.0.set(self.0.get() + by);
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    // A curve with three levels (0 to 2) whose animation runs on `clock`
+    fn animated(clock: &ManualClock) -> ChaikinCurve {
+        let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0)]);
+        curve.clock = Box::new(clock.clone());
+        curve.max_steps = 2;
+        curve
+    }
+
+    fn has_point(points: &[Point2<f32>], x: f32, y: f32) -> bool {
+        points.iter().any(|p| (p - Point2::new(x, y)).norm() < 1e-4)
+    }
+
+    #[test]
+    fn chaikin_step_survives_any_input() {
+        let specials = [f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 0.0, -0.0, f32::MAX, 1e-30];
+        // xorshift64, enough to scatter the coordinates
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for closed in [false, true] {
+            let mut curve = ChaikinCurve::new();
+            curve.closed = closed;
+            for len in 0..50 {
+                for round in 0..20 {
+                    // Every other round is kept finite, the rest mix in the
+                    // special values
+                    let finite = round % 2 == 0;
+                    let mut coordinate = || {
+                        let bits = next();
+                        if !finite && bits % 4 == 0 {
+                            specials[(bits >> 8) as usize % specials.len()]
+                        } else {
+                            ((bits >> 40) as f32 / (1 << 24) as f32 - 0.5) * 2000.0
+                        }
+                    };
+                    let points: Vec<Point2<f32>> =
+                        (0..len).map(|_| Point2::new(coordinate(), coordinate())).collect();
+                    let next_level = curve.chaikin_step(&points);
+                    if finite {
+                        assert!(next_level.iter().all(|p| p.x.is_finite() && p.y.is_finite()));
+                    }
+                }
+            }
+        }
+    }
+
+    fn square() -> ChaikinCurve {
+        let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+        curve.closed = true;
+        curve
+    }
+
+    #[test]
+    fn closed_square_cuts_every_corner() {
+        let curve = square();
+        let polygon: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+        let next = curve.chaikin_step(&polygon);
+        assert_eq!(next.len(), 8);
+        // No corner survives, the closing one included
+        for p in &polygon {
+            assert!(!has_point(&next, p.x, p.y));
+        }
+        // Edges alternate between what is left of a side and a cut corner,
+        // and the last point joins the first like any other pair
+        let cut = 25.0 * 2.0_f32.sqrt();
+        for i in 0..8 {
+            let edge = (next[(i + 1) % 8] - next[i]).norm();
+            let expected = if i % 2 == 0 { 50.0 } else { cut };
+            assert!((edge - expected).abs() < 1e-3, "edge {} is {}", i, edge);
+        }
+    }
+
+    #[test]
+    fn closed_step_is_cyclically_symmetric() {
+        let curve = square();
+        let polygon: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+        let next = curve.chaikin_step(&polygon);
+        // Starting the loop one point later starts the result two points later
+        let mut rotated = polygon.clone();
+        rotated.rotate_left(1);
+        let mut expected = next.clone();
+        expected.rotate_left(2);
+        let rotated_next = curve.chaikin_step(&rotated);
+        for (a, b) in rotated_next.iter().zip(&expected) {
+            assert!((a - b).norm() < 1e-4);
+        }
+        // The square's centre stays the centre
+        let centre = next.iter().fold(Vector2::zeros(), |sum, p| sum + p.coords) / 8.0;
+        assert!((centre - Vector2::new(50.0, 50.0)).norm() < 1e-3);
+    }
+
+    #[test]
+    fn closed_point_count_doubles_up_to_the_step_limit() {
+        let mut curve = square();
+        curve.set_max_steps(STEP_LIMIT + 5);
+        assert_eq!(curve.max_steps, STEP_LIMIT);
+        curve.generate_animation_steps();
+        assert_eq!(curve.animation_steps.len(), STEP_LIMIT + 1);
+        for (step, level) in curve.animation_steps.iter().enumerate() {
+            assert_eq!(level.len(), 4 << step);
+        }
+    }
+
+    #[test]
+    fn moving_touches_only_the_selected_point() {
+        let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (200.0, 0.0)]);
+        let before: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+
+        // Nothing selected: nothing moves
+        curve.translate_selected_points(15.0, -7.0);
+        let unmoved: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+        assert_eq!(unmoved, before);
+
+        assert!(curve.select_point_at(100.0, 0.0));
+        curve.translate_selected_points(15.0, -7.0);
+        let after: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+        assert_eq!(after[0], before[0]);
+        assert_eq!(after[1], Point2::new(115.0, -7.0));
+        assert_eq!(after[2], before[2]);
+    }
+
+    #[test]
+    fn on_step_sees_every_level_in_order() {
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0)]);
+        curve.max_steps = 3;
+        let log = Rc::clone(&seen);
+        curve.on_step = Some(Box::new(move |step, points| {
+            log.borrow_mut().push((step, points.len()));
+        }));
+        curve.generate_animation_steps();
+        assert_eq!(*seen.borrow(), [(0, 3), (1, 6), (2, 12), (3, 24)]);
+    }
+
+    #[test]
+    fn delete_selected_keeps_only_unselected_points() {
+        let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (200.0, 0.0)]);
+        curve.control_points[0].selected = true;
+        curve.control_points[2].selected = true;
+        curve.delete_selected();
+        assert_eq!(curve.control_points.len(), 1);
+        assert_eq!(curve.control_points[0].position, Point2::new(100.0, 0.0));
+
+        curve.control_points[0].selected = true;
+        curve.delete_selected();
+        assert!(curve.control_points.is_empty());
+        assert!(!curve.animating);
+    }
+
+    #[test]
+    fn extending_steps_matches_a_full_regeneration() {
+        let mut extended = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (30.0, 60.0)]);
+        extended.control_points[2].cusp = true;
+        extended.set_segment_ratio(0, 0.1);
+        extended.max_steps = 2;
+        extended.generate_animation_steps();
+        extended.extend_steps(3);
+        assert_eq!(extended.max_steps, 5);
+
+        let mut full = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (30.0, 60.0)]);
+        full.control_points[2].cusp = true;
+        full.set_segment_ratio(0, 0.1);
+        full.max_steps = 5;
+        full.generate_animation_steps();
+        assert_eq!(extended.animation_steps, full.animation_steps);
+    }
+
+    #[test]
+    fn reversed_animation_stops_at_the_polygon() {
+        let clock = ManualClock::new();
+        let mut curve = animated(&clock);
+        curve.looping = false;
+        curve.reverse_direction();
+        curve.start_animation();
+        assert_eq!(curve.current_step, 2);
+
+        let mut steps = Vec::new();
+        for _ in 0..4 {
+            clock.advance(ANIMATION_STEP_DURATION);
+            curve.update_animation();
+            steps.push(curve.current_step);
+        }
+        assert_eq!(steps, [1, 0, 0, 0]);
+        assert!(curve.is_finished());
+    }
+
+    #[test]
+    fn point_limit_is_enforced() {
+        let mut curve = ChaikinCurve::new();
+        curve.max_control_points = Some(2);
+        assert!(curve.add_point(0.0, 0.0));
+        assert!(curve.add_point(10.0, 0.0));
+        assert!(!curve.add_point(20.0, 0.0));
+        assert!(!curve.add_point_snapped(30.0, 0.0, 20.0));
+        assert_eq!(curve.control_points.len(), 2);
+
+        curve.max_control_points = None;
+        assert!(curve.add_point(20.0, 0.0));
+    }
+
+    #[test]
+    fn smoothed_square_contains_its_middle() {
+        let mut curve = square();
+        assert!(curve.contains_point(50.0, 50.0));
+        assert!(curve.contains_point(20.0, 50.0));
+        // Outside the square, and in a corner the smoothing cut off
+        assert!(!curve.contains_point(150.0, 50.0));
+        assert!(!curve.contains_point(-10.0, -10.0));
+        assert!(!curve.contains_point(2.0, 2.0));
+
+        curve.closed = false;
+        assert!(!curve.contains_point(50.0, 50.0));
+    }
+
+    #[test]
+    fn right_angle_is_kept_only_below_the_threshold() {
+        let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0)]);
+        let polygon: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+
+        curve.preserve_angle = Some(100.0_f32.to_radians());
+        let kept = curve.chaikin_step(&polygon);
+        assert!(has_point(&kept, 100.0, 0.0));
+        assert_eq!(kept.len(), 5);
+
+        curve.preserve_angle = Some(80.0_f32.to_radians());
+        let cut = curve.chaikin_step(&polygon);
+        assert!(!has_point(&cut, 100.0, 0.0));
+        assert!(has_point(&cut, 75.0, 0.0) && has_point(&cut, 100.0, 25.0));
+    }
+
+    #[test]
+    fn animation_steps_once_per_step_duration() {
+        let clock = ManualClock::new();
+        let mut curve = animated(&clock);
+        curve.start_animation();
+        assert_eq!(curve.current_step, 0);
+
+        let almost = ANIMATION_STEP_DURATION - Duration::from_millis(1);
+        clock.advance(almost);
+        curve.update_animation();
+        assert_eq!(curve.current_step, 0);
+        clock.advance(Duration::from_millis(1));
+        curve.update_animation();
+        assert_eq!(curve.current_step, 1);
+        // Checking again at the same moment doesn't step twice
+        curve.update_animation();
+        assert_eq!(curve.current_step, 1);
+    }
+
+    #[test]
+    fn stopped_animation_ignores_the_clock() {
+        let clock = ManualClock::new();
+        let mut curve = animated(&clock);
+        curve.start_animation();
+        clock.advance(ANIMATION_STEP_DURATION);
+        curve.update_animation();
+        assert_eq!(curve.current_step, 1);
+
+        curve.animating = false;
+        clock.advance(ANIMATION_STEP_DURATION * 3);
+        curve.update_animation();
+        assert_eq!(curve.current_step, 1);
+    }
+
+    #[test]
+    fn cusp_splits_the_curve_into_independent_halves() {
+        let coords = [(0.0, 0.0), (50.0, 80.0), (100.0, 0.0), (150.0, 80.0), (200.0, 0.0)];
+        let mut whole = curve(&coords);
+        whole.control_points[2].cusp = true;
+        whole.max_steps = 3;
+        let smoothed = whole.final_curve();
+
+        let half = |coords: &[(f32, f32)]| {
+            let mut half = curve(coords);
+            half.max_steps = 3;
+            half.final_curve()
+        };
+        let (left, right) = (half(&coords[..3]), half(&coords[2..]));
+        // The halves meet at the cusp, which both of them keep
+        let mut joined = left.clone();
+        joined.extend_from_slice(&right[1..]);
+        assert_eq!(smoothed.len(), joined.len());
+        for (a, b) in smoothed.iter().zip(&joined) {
+            assert!((a - b).norm() < 1e-3, "{:?} vs {:?}", a, b);
+        }
+        assert!(has_point(&smoothed, 100.0, 0.0));
+    }
+
+    #[test]
+    fn looping_wraps_at_both_ends() {
+        let clock = ManualClock::new();
+        let mut curve = animated(&clock);
+        curve.looping = true;
+        curve.start_animation();
+        curve.step_back();
+        assert_eq!(curve.current_step, 2);
+        curve.step_forward();
+        assert_eq!(curve.current_step, 0);
+        curve.current_step = 2;
+        clock.advance(ANIMATION_STEP_DURATION);
+        curve.update_animation();
+        assert_eq!(curve.current_step, 0);
+    }
+
+    #[test]
+    fn without_looping_steps_stop_at_the_ends() {
+        let clock = ManualClock::new();
+        let mut curve = animated(&clock);
+        curve.looping = false;
+        curve.start_animation();
+        curve.step_back();
+        assert_eq!(curve.current_step, 0);
+        curve.current_step = 2;
+        curve.step_forward();
+        assert_eq!(curve.current_step, 2);
+        clock.advance(ANIMATION_STEP_DURATION);
+        curve.update_animation();
+        assert_eq!(curve.current_step, 2);
+    }
+
+    #[test]
+    fn limit_curve_matches_deep_subdivision() {
+        for closed in [false, true] {
+            let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (130.0, 90.0), (20.0, 120.0)]);
+            curve.closed = closed;
+            curve.max_steps = 8;
+            let limit = curve.limit_curve();
+            let deep = curve.final_curve();
+            assert!(max_deviation_along(&limit, &deep, closed) < 0.05);
+            // And the other way round, so neither runs on past the other; the
+            // limit's samples are further apart, so its chords cut corners
+            assert!(max_deviation(&deep, &limit) < 0.5);
+        }
+    }
+
+    #[test]
+    fn points_exactly_the_merge_distance_apart_stay_separate() {
+        let mut curve = curve(&[(0.0, 0.0), (4.0, 0.0), (50.0, 50.0)]);
+        curve.dedup_epsilon = 4.0;
+        curve.generate_animation_steps();
+        assert_eq!(curve.animation_steps[0].len(), 3);
+
+        // Any closer and they merge into the first
+        curve.control_points[1].position.x = 3.99;
+        curve.generate_animation_steps();
+        assert_eq!(curve.animation_steps[0], [Point2::new(0.0, 0.0), Point2::new(50.0, 50.0)]);
+    }
+
+    #[test]
+    fn refining_bends_leaves_straight_runs_coarse() {
+        let mut curve = curve(&[(0.0, 0.0), (50.0, 0.0), (100.0, 0.0), (100.0, 100.0)]);
+        let polygon: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+        let uniform = curve.chaikin_step(&polygon);
+
+        curve.refine_angle = Some(10.0_f32.to_radians());
+        let refined = curve.chaikin_step(&polygon);
+        assert!(refined.len() < uniform.len());
+        // The point along the straight run stays in place of its two cuts,
+        // while the corner is cut as usual
+        assert!(has_point(&refined, 50.0, 0.0));
+        assert!(!has_point(&refined, 62.5, 0.0));
+        assert!(!has_point(&refined, 100.0, 0.0));
+        assert!(has_point(&refined, 87.5, 0.0) && has_point(&refined, 100.0, 25.0));
+    }
+
+    #[test]
+    fn far_away_points_are_brought_back_near_the_origin() {
+        let far = 1e6;
+        let mut curve = curve(&[(far, far), (far + 40.0, far), (far + 40.0, far + 40.0)]);
+        let before: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+        let shift = curve.recenter(20.0).expect("points a million pixels out are recentered");
+        // A whole number of grid cells, and the same shape where f32 has
+        // precision to spare
+        assert_eq!(shift.map(|v| v % 20.0), Vector2::zeros());
+        for (cp, old) in curve.control_points.iter().zip(&before) {
+            assert_eq!(cp.position + shift, *old);
+            assert!(cp.position.coords.norm() < 100.0);
+        }
+        assert!(curve.recenter(20.0).is_none());
+
+        // Sub-pixel edits survive near the origin, where a step of 0.01 at
+        // a million would be lost
+        assert_eq!(far + 0.01, far);
+        let x = curve.control_points[0].position.x;
+        curve.control_points[0].selected = true;
+        curve.translate_selected_points(0.01, 0.0);
+        assert!((curve.control_points[0].position.x - x - 0.01).abs() < 1e-4);
+    }
+
+    #[test]
+    fn centroid_is_the_mean_of_the_control_points() {
+        assert_eq!(ChaikinCurve::new().centroid(), None);
+        assert_eq!(square().centroid(), Some(Point2::new(50.0, 50.0)));
+        // Uneven spacing pulls it towards the crowded side
+        let lopsided = curve(&[(0.0, 0.0), (10.0, 0.0), (20.0, 0.0), (90.0, 30.0)]);
+        assert_eq!(lopsided.centroid(), Some(Point2::new(30.0, 7.5)));
+    }
+
+    #[test]
+    fn toggling_closed_keeps_the_points_and_regenerates_the_steps() {
+        let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+        curve.start_animation();
+        let points: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+        for _ in 0..4 {
+            curve.toggle_closed();
+            let after: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+            assert_eq!(after, points);
+
+            let steps = curve.animation_steps.clone();
+            curve.generate_animation_steps();
+            assert_eq!(steps, curve.animation_steps);
+            // The closed curve wraps round, so it has points the open one lacks
+            assert_eq!(has_point(&steps[1], 0.0, 75.0), curve.is_closed());
+        }
+    }
+
+    #[test]
+    fn level_functions_agree() {
+        let points: Vec<_> = [(0.0, 0.0), (100.0, 20.0), (140.0, 120.0), (30.0, 90.0)]
+            .iter()
+            .map(|&(x, y)| Point2::new(x, y))
+            .collect();
+        let levels = chaikin_all_levels(&points, 5);
+        assert_eq!(levels.len(), 6);
+        for (k, level) in levels.iter().enumerate() {
+            assert_eq!(*level, chaikin_level(&points, k));
+        }
+        assert_eq!(chaikin_final(&points, 5), levels[5]);
+        // The same as stepping a fresh curve
+        assert_eq!(levels[1], ChaikinCurve::new().chaikin_step(&points));
+    }
+
+    #[test]
+    fn zero_ratio_segment_keeps_its_corner() {
+        let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+        let polygon: Vec<_> = curve.control_points.iter().map(|cp| cp.position).collect();
+        assert!(!has_point(&curve.chaikin_step(&polygon), 100.0, 0.0));
+
+        curve.set_segment_ratio(0, 0.0);
+        let next = curve.chaikin_step(&polygon);
+        // The first segment is cut at its ends, so the corner it runs into
+        // survives, while the second is still cut a quarter of the way in
+        assert!(has_point(&next, 100.0, 0.0));
+        assert!(has_point(&next, 100.0, 25.0));
+        assert!(has_point(&next, 100.0, 75.0));
+    }
+
+    #[test]
+    fn convex_hull_leaves_out_inner_points() {
+        let curve = curve(&[(0.0, 0.0), (50.0, 20.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+        let hull = curve.convex_hull();
+        assert_eq!(hull.len(), 4);
+        for (x, y) in [(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)] {
+            assert!(has_point(&hull, x, y));
+        }
+        assert!(!has_point(&hull, 50.0, 20.0));
+    }
+
+    #[test]
+    fn step_error_shrinks_with_every_step() {
+        for closed in [false, true] {
+            let mut curve = curve(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+            curve.closed = closed;
+            curve.max_steps = 6;
+            let errors: Vec<f32> = (0..=6).map(|step| curve.step_error(step)).collect();
+            for pair in errors.windows(2) {
+                assert!(pair[1] < pair[0], "closed {}: {:?}", closed, errors);
+            }
+        }
+    }
+
+    #[test]
+    fn segment_ratios_follow_deleted_and_moved_points() {
+        let mut curve = curve(&[(0.0, 0.0), (10.0, 0.0), (20.0, 0.0), (30.0, 0.0)]);
+        curve.set_segment_ratio(2, 0.1);
+        curve.control_points[1].selected = true;
+        curve.delete_selected();
+        assert_eq!(curve.segment_ratio(1), 0.1);
+        assert_eq!(curve.segment_ratio(0), DEFAULT_RATIO);
+
+        curve.move_point(1, 0);
+        assert_eq!(curve.segment_ratio(0), 0.1);
+        assert_eq!(curve.segment_ratio(1), DEFAULT_RATIO);
+    }
+}
@@ -0,0 +1,363 @@
+use chaikin::geometry::distance_to_segment;
+pub use chaikin::render::{
+    Canvas, blend_color, blend_point, draw_circle, draw_line, draw_point, draw_polyline,
+    draw_segment,
+};
+use nalgebra::{Point2, Vector2};
+
+// Box filter from a buffer `factor` times the target's size in each
+// direction into the target: each pixel is the average of its block
+pub fn downsample(source: &[u32], target: &mut Canvas, factor: usize) {
+    let source_width = target.width * factor;
+    let samples = (factor * factor) as u32;
+    for y in 0..target.height {
+        for x in 0..target.width {
+            let mut sum = [0u32; 3];
+            for sy in 0..factor {
+                let row = (y * factor + sy) * source_width + x * factor;
+                for &pixel in &source[row..row + factor] {
+                    sum[0] += (pixel >> 16) & 0xFF;
+                    sum[1] += (pixel >> 8) & 0xFF;
+                    sum[2] += pixel & 0xFF;
+                }
+            }
+            let [r, g, b] = sum.map(|channel| channel / samples);
+            target.pixels[y * target.width + x] = 0xFF000000 | (r << 16) | (g << 8) | b;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MarkerShape {
+    Circle,
+    Square,
+    Diamond,
+    // A diagonal cross, with arms three pixels thick
+    Cross,
+}
+
+impl MarkerShape {
+    pub fn next(self) -> Self {
+        match self {
+            MarkerShape::Circle => MarkerShape::Square,
+            MarkerShape::Square => MarkerShape::Diamond,
+            MarkerShape::Diamond => MarkerShape::Cross,
+            MarkerShape::Cross => MarkerShape::Circle,
+        }
+    }
+}
+
+// Filled marker of the given shape reaching `size` pixels from its centre
+pub fn draw_marker(canvas: &mut Canvas, x: i32, y: i32, size: i32, shape: MarkerShape, color: u32) {
+    let inside = |dx: i32, dy: i32| match shape {
+        MarkerShape::Circle => dx * dx + dy * dy <= size * size,
+        MarkerShape::Square => true,
+        MarkerShape::Diamond => dx.abs() + dy.abs() <= size,
+        MarkerShape::Cross => (dx - dy).abs() <= 1 || (dx + dy).abs() <= 1,
+    };
+    for dy in -size..=size {
+        for dx in -size..=size {
+            if inside(dx, dy) {
+                draw_point(canvas, x + dx, y + dy, color);
+            }
+        }
+    }
+}
+
+// Same shapes as `draw_marker`, centred anywhere between pixels and with
+// their edges blended by how much of each pixel they cover
+pub fn draw_marker_aa(
+    canvas: &mut Canvas,
+    center: Point2<f32>,
+    size: f32,
+    shape: MarkerShape,
+    color: u32,
+) {
+    // Reaches half a pixel further than the crisp marker's outermost centres
+    let reach = size + 0.5;
+    fill_coverage(canvas, center, reach, color, |dx, dy| match shape {
+        MarkerShape::Circle => dx * dx + dy * dy <= reach * reach,
+        MarkerShape::Square => true,
+        MarkerShape::Diamond => dx.abs() + dy.abs() <= reach,
+        MarkerShape::Cross => (dx - dy).abs() <= 1.5 || (dx + dy).abs() <= 1.5,
+    });
+}
+
+// Anti-aliased `draw_ring`
+pub fn draw_ring_aa(canvas: &mut Canvas, center: Point2<f32>, radius: f32, color: u32) {
+    fill_coverage(canvas, center, radius + 0.5, color, |dx, dy| {
+        let d = (dx * dx + dy * dy).sqrt();
+        d > radius - 0.5 && d <= radius + 0.5
+    });
+}
+
+// Blends `color` into every pixel within `reach` of `center` by the share of
+// a grid of samples across the pixel for which `inside` holds, given the
+// sample's offset from the centre
+fn fill_coverage(
+    canvas: &mut Canvas,
+    center: Point2<f32>,
+    reach: f32,
+    color: u32,
+    inside: impl Fn(f32, f32) -> bool,
+) {
+    const GRID: usize = 4;
+    let left = (center.x - reach).floor() as i32;
+    let right = (center.x + reach).ceil() as i32;
+    let top = (center.y - reach).floor() as i32;
+    let bottom = (center.y + reach).ceil() as i32;
+    for y in top..=bottom {
+        for x in left..=right {
+            let mut hits = 0;
+            for sy in 0..GRID {
+                for sx in 0..GRID {
+                    // Pixel x covers x - 0.5 to x + 0.5, as in `draw_marker`
+                    let offset = |i: usize| (i as f32 + 0.5) / GRID as f32 - 0.5;
+                    let dx = x as f32 + offset(sx) - center.x;
+                    let dy = y as f32 + offset(sy) - center.y;
+                    if dx.abs() <= reach && dy.abs() <= reach && inside(dx, dy) {
+                        hits += 1;
+                    }
+                }
+            }
+            if hits > 0 {
+                blend_point(canvas, x, y, color, hits as f32 / (GRID * GRID) as f32);
+            }
+        }
+    }
+}
+
+// One pixel wide outline of a circle
+pub fn draw_ring(canvas: &mut Canvas, center_x: i32, center_y: i32, radius: i32, color: u32) {
+    let inner = (radius - 1) * (radius - 1);
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            let d = x * x + y * y;
+            if d > inner && d <= radius * radius {
+                draw_point(canvas, center_x + x, center_y + y, color);
+            }
+        }
+    }
+}
+
+// Segment from `a` to `b` with an open arrowhead of `head` pixels at `b`
+pub fn draw_arrow(canvas: &mut Canvas, a: Point2<f32>, b: Point2<f32>, head: f32, color: u32) {
+    draw_segment(canvas, a, b, color);
+    let Some(back) = (a - b).try_normalize(f32::EPSILON) else {
+        return;
+    };
+    // The two barbs sit 30 degrees either side of the shaft
+    let (sin, cos) = std::f32::consts::FRAC_PI_6.sin_cos();
+    for side in [-1.0, 1.0] {
+        let barb = Vector2::new(
+            back.x * cos - back.y * sin * side,
+            back.x * sin * side + back.y * cos,
+        );
+        draw_segment(canvas, b, b + barb * head, color);
+    }
+}
+
+// Polyline drawn as dashes of `dash` pixels with gaps of the same length. The
+// pattern carries on across corners so short segments still show gaps, and
+// starts `offset` pixels along the line, so a growing offset moves the dashes
+// forward.
+pub fn draw_dashed_polyline(
+    canvas: &mut Canvas,
+    points: &[Point2<f32>],
+    dash: f32,
+    offset: f32,
+    color: u32,
+) {
+    let period = dash * 2.0;
+    let offset = offset.rem_euclid(period);
+    // Distance along the whole line where the current segment starts
+    let mut travelled = 0.0;
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let length = (b - a).norm();
+        let at = |distance: f32| a + (b - a) * ((distance - travelled) / length);
+        // Every dash overlapping this segment, by where it starts along the line
+        let mut n = ((travelled - offset) / period).floor() as i32;
+        while n as f32 * period + offset < travelled + length {
+            let dash_start = n as f32 * period + offset;
+            let start = dash_start.max(travelled);
+            let end = (dash_start + dash).min(travelled + length);
+            if start < end {
+                draw_segment(canvas, at(start), at(end), color);
+            }
+            n += 1;
+        }
+        travelled += length;
+    }
+}
+
+// Wu's anti-aliased line. The endpoints keep their fractional positions, so
+// slow sub-pixel movement shows up as a smooth shift rather than a jump.
+pub fn draw_line_aa(canvas: &mut Canvas, a: Point2<f32>, b: Point2<f32>, color: u32) {
+    let fract = |v: f32| v - v.floor();
+
+    let steep = (b.y - a.y).abs() > (b.x - a.x).abs();
+    // Walk along the major axis, which after this swap is always x
+    let (mut x0, mut y0, mut x1, mut y1) = if steep {
+        (a.y, a.x, b.y, b.x)
+    } else {
+        (a.x, a.y, b.x, b.y)
+    };
+    if x0 > x1 {
+        std::mem::swap(&mut x0, &mut x1);
+        std::mem::swap(&mut y0, &mut y1);
+    }
+
+    let dx = x1 - x0;
+    let gradient = if dx.abs() < f32::EPSILON { 1.0 } else { (y1 - y0) / dx };
+    let mut plot = |x: i32, y: i32, coverage: f32| {
+        if steep {
+            blend_point(canvas, y, x, color, coverage);
+        } else {
+            blend_point(canvas, x, y, color, coverage);
+        }
+    };
+
+    // First endpoint
+    let x_end = x0.round();
+    let y_end = y0 + gradient * (x_end - x0);
+    let x_gap = 1.0 - fract(x0 + 0.5);
+    let x_start = x_end as i32;
+    plot(x_start, y_end.floor() as i32, (1.0 - fract(y_end)) * x_gap);
+    plot(x_start, y_end.floor() as i32 + 1, fract(y_end) * x_gap);
+    let mut y = y_end + gradient;
+
+    // Second endpoint
+    let x_end = x1.round();
+    let y_end = y1 + gradient * (x_end - x1);
+    let x_gap = fract(x1 + 0.5);
+    let x_stop = x_end as i32;
+    plot(x_stop, y_end.floor() as i32, (1.0 - fract(y_end)) * x_gap);
+    plot(x_stop, y_end.floor() as i32 + 1, fract(y_end) * x_gap);
+
+    for x in x_start + 1..x_stop {
+        plot(x, y.floor() as i32, 1.0 - fract(y));
+        plot(x, y.floor() as i32 + 1, fract(y));
+        y += gradient;
+    }
+}
+
+pub fn draw_polyline_aa(canvas: &mut Canvas, points: &[Point2<f32>], color: u32) {
+    for segment in points.windows(2) {
+        draw_line_aa(canvas, segment[0], segment[1], color);
+    }
+}
+
+// The fully saturated, full brightness colour at `hue` degrees round the
+// colour wheel: 0 is red, 120 green and 240 blue
+pub fn hue_color(hue: f32) -> u32 {
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+    let channel = |c: f32| (c * 255.0).round() as u32;
+    0xFF000000 | channel(r) << 16 | channel(g) << 8 | channel(b)
+}
+
+// Soft halo around a polyline, fading out linearly over `radius` pixels.
+// Coverage is collected in a mask first so overlapping segments don't stack
+// up into brighter blotches at the joints. The mask only spans the curve's
+// bounds plus the radius, and lives in `mask` so its memory is reused from
+// one frame to the next.
+pub fn draw_glow(
+    canvas: &mut Canvas,
+    mask: &mut Vec<f32>,
+    points: &[Point2<f32>],
+    radius: f32,
+    color: u32,
+    alpha: f32,
+) {
+    if points.len() < 2 {
+        return;
+    }
+    let (min, max) = points.iter().fold((points[0], points[0]), |(min, max), p| {
+        (min.inf(p), max.sup(p))
+    });
+    let left = ((min.x - radius).floor() as i32).max(0);
+    let top = ((min.y - radius).floor() as i32).max(0);
+    let right = ((max.x + radius).ceil() as i32).min(canvas.width as i32 - 1);
+    let bottom = ((max.y + radius).ceil() as i32).min(canvas.height as i32 - 1);
+    if left > right || top > bottom {
+        return;
+    }
+    let mask_width = (right - left + 1) as usize;
+    mask.clear();
+    mask.resize(mask_width * (bottom - top + 1) as usize, 0.0);
+
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let min_x = ((a.x.min(b.x) - radius).floor() as i32).max(left);
+        let max_x = ((a.x.max(b.x) + radius).ceil() as i32).min(right);
+        let min_y = ((a.y.min(b.y) - radius).floor() as i32).max(top);
+        let max_y = ((a.y.max(b.y) + radius).ceil() as i32).min(bottom);
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let distance = distance_to_segment(Point2::new(x as f32, y as f32), a, b);
+                let strength = 1.0 - distance / radius;
+                let cell = &mut mask[(y - top) as usize * mask_width + (x - left) as usize];
+                if strength > *cell {
+                    *cell = strength;
+                }
+            }
+        }
+    }
+
+    for (row, strengths) in mask.chunks_exact(mask_width).enumerate() {
+        let start = (top as usize + row) * canvas.width + left as usize;
+        let pixels = &mut canvas.pixels[start..start + mask_width];
+        for (pixel, &strength) in pixels.iter_mut().zip(strengths) {
+            if strength > 0.0 {
+                *pixel = blend_color(*pixel, color, alpha * strength);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polylines_of_fewer_than_two_points_draw_nothing() {
+        let mut buffer = vec![0; 64 * 48];
+        let mut canvas = Canvas::new(&mut buffer, 64, 48);
+        let one = [Point2::new(10.0, 10.0)];
+        for points in [&[][..], &one[..]] {
+            draw_polyline(&mut canvas, points, 0xFFFFFFFF);
+            draw_polyline_aa(&mut canvas, points, 0xFFFFFFFF);
+            draw_dashed_polyline(&mut canvas, points, 4.0, 1.0, 0xFFFFFFFF);
+            draw_glow(&mut canvas, &mut Vec::new(), points, 5.0, 0xFFFFFFFF, 0.5);
+        }
+        assert!(buffer.iter().all(|&pixel| pixel == 0));
+    }
+
+    #[test]
+    fn glow_fades_out_over_its_radius() {
+        let (width, height) = (40, 20);
+        let mut buffer = vec![0xFF000000; width * height];
+        let mut canvas = Canvas::new(&mut buffer, width, height);
+        // Left over from a bigger glow, which mustn't show through
+        let mut mask = vec![1.0; width * height * 2];
+        let line = [Point2::new(10.0, 10.0), Point2::new(30.0, 10.0)];
+        draw_glow(&mut canvas, &mut mask, &line, 4.0, 0xFFFFFFFF, 1.0);
+
+        let pixel = |x: usize, y: usize| buffer[y * width + x];
+        assert_eq!(pixel(20, 10), 0xFFFFFFFF);
+        assert_eq!(pixel(20, 12), blend_color(0xFF000000, 0xFFFFFFFF, 0.5));
+        assert_eq!(pixel(20, 14), 0xFF000000);
+        assert_eq!(pixel(2, 10), 0xFF000000);
+        assert_eq!(pixel(20, 0), 0xFF000000);
+    }
+}
@@ -0,0 +1,72 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use nalgebra::Point2;
+
+// How long a new point takes to grow to full size, and a removed one to
+// shrink away
+const EFFECT_DURATION: Duration = Duration::from_millis(150);
+
+// Short animations for control points that were just added or removed. The
+// points have no identity of their own, so they are recognised by position:
+// a point that is dragged straight after being placed simply stops growing.
+pub struct PointEffects {
+    appearing: Vec<(Point2<f32>, Instant)>,
+    vanishing: Vec<(Point2<f32>, Instant)>,
+}
+
+impl PointEffects {
+    pub fn new() -> Self {
+        Self {
+            appearing: Vec::new(),
+            vanishing: Vec::new(),
+        }
+    }
+
+    // Compares the control point positions from before and after an edit.
+    // Only edits that change the number of points count, so moving points
+    // around doesn't set anything off.
+    pub fn compare(&mut self, before: &[Point2<f32>], after: &[Point2<f32>]) {
+        let key = |p: &Point2<f32>| (p.x.to_bits(), p.y.to_bits());
+        let now = Instant::now();
+        if after.len() > before.len() {
+            let old: HashSet<_> = before.iter().map(key).collect();
+            self.appearing
+                .extend(after.iter().filter(|p| !old.contains(&key(p))).map(|&p| (p, now)));
+        } else if after.len() < before.len() {
+            let kept: HashSet<_> = after.iter().map(key).collect();
+            self.vanishing
+                .extend(before.iter().filter(|p| !kept.contains(&key(p))).map(|&p| (p, now)));
+        }
+    }
+
+    // Drops the effects that have run their course
+    pub fn prune(&mut self) {
+        let running = |&(_, start): &(Point2<f32>, Instant)| start.elapsed() < EFFECT_DURATION;
+        self.appearing.retain(running);
+        self.vanishing.retain(running);
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.appearing.is_empty() || !self.vanishing.is_empty()
+    }
+
+    // How large the point at `position` is drawn, from 0 when it has just
+    // been added up to 1
+    pub fn scale_at(&self, position: Point2<f32>) -> f32 {
+        self.appearing
+            .iter()
+            .find(|(p, _)| *p == position)
+            .map_or(1.0, |&(_, start)| progress(start))
+    }
+
+    // The removed points still on their way out, with how much of them is
+    // left, from 1 down to 0
+    pub fn vanishing(&self) -> impl Iterator<Item = (Point2<f32>, f32)> + '_ {
+        self.vanishing.iter().map(|&(p, start)| (p, 1.0 - progress(start)))
+    }
+}
+
+fn progress(start: Instant) -> f32 {
+    (start.elapsed().as_secs_f32() / EFFECT_DURATION.as_secs_f32()).min(1.0)
+}
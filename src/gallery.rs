@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chaikin::ChaikinCurve;
+
+// Flips through the saved curves (`.json` files) in the current directory,
+// in name order. Files that don't hold points, like an exported step list,
+// are skipped over.
+pub struct Gallery {
+    // The file shown last, used to find the next one even after files are
+    // added or removed
+    current: Option<PathBuf>,
+}
+
+impl Gallery {
+    pub fn new() -> Self {
+        Self { current: None }
+    }
+
+    // Loads the next (or previous) curve file into `chaikin`, wrapping round
+    // at the ends. Returns the file loaded, or `None` if none could be.
+    pub fn step(&mut self, chaikin: &mut ChaikinCurve, forward: bool) -> Option<PathBuf> {
+        let files = curve_files(Path::new("."));
+        if files.is_empty() {
+            return None;
+        }
+
+        let count = files.len();
+        // Where the current file is, or would be in name order if it is gone
+        let start = match &self.current {
+            Some(current) => match files.binary_search(current) {
+                Ok(index) => index,
+                Err(index) if forward => index + count - 1,
+                Err(index) => index,
+            },
+            None if forward => count - 1,
+            None => 0,
+        };
+
+        for offset in 1..=count {
+            let index = if forward {
+                (start + offset) % count
+            } else {
+                (start + count * 2 - offset) % count
+            };
+            let path = &files[index];
+            match chaikin.load_points(path) {
+                Ok(()) => {
+                    self.current = Some(path.clone());
+                    return Some(path.clone());
+                }
+                Err(err) => eprintln!("Skipping {}: {}", path.display(), err),
+            }
+        }
+        None
+    }
+}
+
+fn curve_files(dir: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            eprintln!("Could not list {}: {}", dir.display(), err);
+            return Vec::new();
+        }
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "json"))
+        .collect();
+    files.sort();
+    files
+}
@@ -0,0 +1,309 @@
+use nalgebra::{Point2, Vector2};
+
+/// Shortest distance from `p` to the segment `a`-`b`.
+pub fn distance_to_segment(p: Point2<f32>, a: Point2<f32>, b: Point2<f32>) -> f32 {
+    let ab = b - a;
+    let length_sq = ab.norm_squared();
+    let t = if length_sq > 0.0 {
+        ((p - a).dot(&ab) / length_sq).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    (p - (a + ab * t)).norm()
+}
+
+/// The farthest any of `points` lies from the polyline through `path`.
+pub fn max_deviation(points: &[Point2<f32>], path: &[Point2<f32>]) -> f32 {
+    let distance = |p: Point2<f32>| match path {
+        [] => 0.0,
+        [only] => (p - only).norm(),
+        _ => path
+            .windows(2)
+            .map(|segment| distance_to_segment(p, segment[0], segment[1]))
+            .fold(f32::INFINITY, f32::min),
+    };
+    points.iter().map(|&p| distance(p)).fold(0.0, f32::max)
+}
+
+/// Like `max_deviation`, for `points` running along `path` in the same
+/// direction, such as samples of a curve against a polyline approximating
+/// it. Each point is only compared with the segments just ahead of the
+/// previous point's nearest one, so this takes time in proportion to the
+/// two lengths instead of their product. A `closed` path also has the
+/// segment from its last point back to its first.
+pub fn max_deviation_along(points: &[Point2<f32>], path: &[Point2<f32>], closed: bool) -> f32 {
+    let segments = if closed { path.len() } else { path.len().saturating_sub(1) };
+    let Some(&first) = points.first() else {
+        return 0.0;
+    };
+    if segments == 0 {
+        return max_deviation(points, path);
+    }
+    let distance = |p: Point2<f32>, segment: usize| {
+        distance_to_segment(p, path[segment % path.len()], path[(segment + 1) % path.len()])
+    };
+    let nearest_of = |p: Point2<f32>, candidates: std::ops::RangeInclusive<usize>| {
+        candidates
+            .map(|segment| (segment % segments, distance(p, segment)))
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .unwrap_or((0, 0.0))
+    };
+
+    // Far enough ahead to cover the segments between two points, twice over
+    let lookahead = segments.div_ceil(points.len()) * 2 + 2;
+    let (mut nearest, _) = nearest_of(first, 0..=segments - 1);
+    let mut worst: f32 = 0.0;
+    for &p in points {
+        let ahead = nearest + lookahead;
+        let last = if closed { ahead } else { ahead.min(segments - 1) };
+        let (segment, d) = nearest_of(p, nearest..=last);
+        nearest = segment;
+        worst = worst.max(d);
+    }
+    worst
+}
+
+/// Ramer–Douglas–Peucker simplification. Returns the indices of the points to
+/// keep, in order; the first and last point are always kept.
+pub fn simplify_indices(points: &[Point2<f32>], epsilon: f32) -> Vec<usize> {
+    if points.len() <= 2 {
+        return (0..points.len()).collect();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+
+    // Ranges still to be examined, as (first, last) index pairs
+    let mut pending = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = pending.pop() {
+        let (a, b) = (points[first], points[last]);
+        let farthest = (first + 1..last)
+            .map(|i| (i, distance_to_segment(points[i], a, b)))
+            .max_by(|x, y| x.1.total_cmp(&y.1));
+
+        if let Some((index, distance)) = farthest
+            && distance > epsilon
+        {
+            keep[index] = true;
+            pending.push((first, index));
+            pending.push((index, last));
+        }
+    }
+
+    keep.iter()
+        .enumerate()
+        .filter(|&(_, &k)| k)
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Curvature (1 / radius) of the circle through `points[index]` and its two
+/// neighbours. Endpoints and degenerate triples have zero curvature.
+pub fn curvature_at(points: &[Point2<f32>], index: usize) -> f32 {
+    if index == 0 || index + 1 >= points.len() {
+        return 0.0;
+    }
+
+    let (a, b, c) = (points[index - 1], points[index], points[index + 1]);
+    let ab = b - a;
+    let bc = c - b;
+    let ca = a - c;
+    let denominator = ab.norm() * bc.norm() * ca.norm();
+    if denominator <= f32::EPSILON {
+        return 0.0;
+    }
+
+    // Menger curvature: 4 * triangle area / product of the side lengths
+    let twice_area = (ab.x * bc.y - ab.y * bc.x).abs();
+    2.0 * twice_area / denominator
+}
+
+/// Whether every point lies within `epsilon` of the line through the first
+/// and last point (or, if those coincide, through the first point and the one
+/// farthest from it). Any set of two or fewer points is collinear.
+pub fn are_collinear(points: &[Point2<f32>], epsilon: f32) -> bool {
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+        return true;
+    };
+    let far = if (last - first).norm() > epsilon {
+        last
+    } else {
+        points
+            .iter()
+            .copied()
+            .max_by(|a, b| (a - first).norm().total_cmp(&(b - first).norm()))
+            .unwrap_or(last)
+    };
+
+    let Some(direction) = (far - first).try_normalize(f32::EPSILON) else {
+        // Every point is on top of the first one
+        return true;
+    };
+    points.iter().all(|p| {
+        let offset = p - first;
+        (offset.x * direction.y - offset.y * direction.x).abs() <= epsilon
+    })
+}
+
+/// Interior angle in radians at `corner` between the edges to `prev` and
+/// `next`: π for a straight run, approaching 0 for a hairpin. `None` when
+/// either edge has zero length.
+pub fn corner_angle(prev: Point2<f32>, corner: Point2<f32>, next: Point2<f32>) -> Option<f32> {
+    let a = (prev - corner).try_normalize(f32::EPSILON)?;
+    let b = (next - corner).try_normalize(f32::EPSILON)?;
+    Some(a.dot(&b).clamp(-1.0, 1.0).acos())
+}
+
+/// Unit tangent direction at `points[index]`: the average direction of the
+/// two adjacent segments, or of the single segment at an endpoint. `None` when
+/// the point has no neighbour or the direction cancels out.
+pub fn tangent_at(points: &[Point2<f32>], index: usize) -> Option<Vector2<f32>> {
+    let unit = |v: Vector2<f32>| v.try_normalize(f32::EPSILON);
+    let incoming = index
+        .checked_sub(1)
+        .and_then(|prev| unit(points[index] - points[prev]));
+    let outgoing = points
+        .get(index + 1)
+        .and_then(|next| unit(next - points[index]));
+
+    match (incoming, outgoing) {
+        (Some(a), Some(b)) => unit(a + b),
+        (Some(a), None) => Some(a),
+        (None, Some(b)) => Some(b),
+        (None, None) => None,
+    }
+}
+
+/// Even-odd rule: casts a ray to the right of `p` and counts how many edges of
+/// the (implicitly closed) polygon it crosses.
+pub fn point_in_polygon(p: Point2<f32>, polygon: &[Point2<f32>]) -> bool {
+    if polygon.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    let mut j = polygon.len() - 1;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[j]);
+        if (a.y > p.y) != (b.y > p.y) {
+            let crossing_x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if p.x < crossing_x {
+                inside = !inside;
+            }
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Andrew's monotone chain. Returns the corners of the convex hull in order,
+/// without repeating the first one and skipping points along an edge. Fewer
+/// than three distinct points come back as they are, deduplicated.
+pub fn convex_hull(points: &[Point2<f32>]) -> Vec<Point2<f32>> {
+    let mut sorted = points.to_vec();
+    sorted.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    sorted.dedup();
+    if sorted.len() < 3 {
+        return sorted;
+    }
+
+    // Walks the points keeping only the ones where the chain turns the same
+    // way, which leaves one side of the hull
+    let chain = |points: &mut dyn Iterator<Item = Point2<f32>>| {
+        let mut chain: Vec<Point2<f32>> = Vec::new();
+        for p in points {
+            while let [.., o, a] = chain[..] {
+                if (a.x - o.x) * (p.y - o.y) - (a.y - o.y) * (p.x - o.x) > 0.0 {
+                    break;
+                }
+                chain.pop();
+            }
+            chain.push(p);
+        }
+        // The last point starts the other chain
+        chain.pop();
+        chain
+    };
+
+    let mut hull = chain(&mut sorted.iter().copied());
+    hull.extend(chain(&mut sorted.iter().rev().copied()));
+    hull
+}
+
+/// Points spaced `spacing` apart along the polyline through `points`,
+/// measured by arc length, from its first point to exactly its last. Corners
+/// between the samples are cut, so a small spacing follows the line closely.
+pub fn resample_by_arc_length(points: &[Point2<f32>], spacing: f32) -> Vec<Point2<f32>> {
+    let (Some(&first), Some(&last)) = (points.first(), points.last()) else {
+        return Vec::new();
+    };
+    if spacing <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut result = vec![first];
+    // How far along the current segment the next sample falls
+    let mut next = spacing;
+    for segment in points.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        let length = (b - a).norm();
+        while next <= length {
+            result.push(a + (b - a) * (next / length));
+            next += spacing;
+        }
+        next -= length;
+    }
+    // Finish at the real end unless the last sample already landed on it
+    if result.last().is_some_and(|&p| (p - last).norm() > spacing * 0.5) {
+        result.push(last);
+    } else if let Some(end) = result.last_mut() {
+        *end = last;
+    }
+    result
+}
+
+/// `count` points evenly spaced round a circle, starting straight up from
+/// `center` and going clockwise on screen (y grows downwards).
+pub fn ring_points(center: Point2<f32>, radius: f32, count: usize) -> Vec<Point2<f32>> {
+    (0..count)
+        .map(|i| {
+            let angle = std::f32::consts::TAU * i as f32 / count as f32;
+            center + Vector2::new(angle.sin(), -angle.cos()) * radius
+        })
+        .collect()
+}
+
+/// Total length of the polyline through `points`, including the segment from
+/// the last point back to the first when `closed`.
+pub fn curve_length(points: &[Point2<f32>], closed: bool) -> f32 {
+    let open: f32 = points.windows(2).map(|s| (s[1] - s[0]).norm()).sum();
+    match (points.first(), points.last()) {
+        (Some(&first), Some(&last)) if closed => open + (first - last).norm(),
+        _ => open,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(coords: &[(f32, f32)]) -> Vec<Point2<f32>> {
+        coords.iter().map(|&(x, y)| Point2::new(x, y)).collect()
+    }
+
+    #[test]
+    fn collinear_points_are_detected() {
+        let line = points(&[(0.0, 0.0), (30.0, 15.2), (80.0, 39.9), (100.0, 50.0)]);
+        assert!(are_collinear(&line, 0.5));
+        // Doubling back along the line still counts
+        let back = points(&[(0.0, 0.0), (100.0, 0.0), (40.0, 0.0)]);
+        assert!(are_collinear(&back, 0.5));
+    }
+
+    #[test]
+    fn bent_points_are_not_collinear() {
+        let bent = points(&[(0.0, 0.0), (50.0, 5.0), (100.0, 0.0)]);
+        assert!(!are_collinear(&bent, 0.5));
+    }
+}
@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use minifb::Key;
+
+use crate::actions::Action;
+use crate::bindings::help_rows;
+use crate::draw::{Canvas, blend_point};
+use crate::text::{GLYPH_HEIGHT, draw_text, text_width};
+use crate::{HEIGHT, WIDTH};
+
+// Mouse and arrow key controls, which aren't remappable; the key bindings
+// are listed after these from whatever is currently bound
+const FIXED_BINDINGS: &[(&str, &str)] = &[
+    ("Click", "Add or select a point"),
+    ("Drag", "Move the selected points"),
+    ("Shift+Drag", "Move along one axis only"),
+    ("Ctrl+Click", "Add or remove from the selection"),
+    ("Alt+Drag", "Size a ring of points on an empty canvas"),
+    ("Minimap click", "Centre the view there"),
+    ("Arrows", "Nudge the selected points"),
+    ("Right drag", "Pan the view"),
+    ("Scroll", "Zoom around the cursor"),
+];
+
+const PANEL_COLOR: u32 = 0xFF101018;
+const PANEL_ALPHA: f32 = 0.85;
+const KEY_COLOR: u32 = 0xFFFFD060;
+const ACTION_COLOR: u32 = 0xFFCCCCCC;
+const PADDING: i32 = 10;
+const LINE_SPACING: i32 = 3;
+// Gap between the key column and the action column
+const COLUMN_GAP: i32 = 12;
+// Gap between side by side columns once the list is too long for one
+const SECTION_GAP: i32 = 24;
+// Space kept clear above and below the panel
+const WINDOW_MARGIN: i32 = 10;
+
+// Darkened panel in the middle of the window listing every binding
+pub fn draw_help(canvas: &mut Canvas, bindings: &HashMap<Key, Action>) {
+    let rows: Vec<(String, &str)> = FIXED_BINDINGS
+        .iter()
+        .map(|&(key, action)| (key.to_string(), action))
+        .chain(help_rows(bindings))
+        .collect();
+    let key_width = rows.iter().map(|(key, _)| text_width(key)).max().unwrap_or(0);
+    let action_width = rows
+        .iter()
+        .map(|(_, action)| text_width(action))
+        .max()
+        .unwrap_or(0);
+    let line_height = GLYPH_HEIGHT + LINE_SPACING;
+
+    // Splits the list into as many columns as it takes to fit the window
+    let fit = (HEIGHT as i32 - (WINDOW_MARGIN + PADDING) * 2 + LINE_SPACING) / line_height;
+    let columns = rows.len().div_ceil(fit.max(1) as usize).max(1);
+    let per_column = rows.len().div_ceil(columns).max(1);
+    let column_width = key_width + COLUMN_GAP + action_width;
+
+    let w = column_width * columns as i32 + SECTION_GAP * (columns as i32 - 1) + PADDING * 2;
+    let h = per_column as i32 * line_height - LINE_SPACING + PADDING * 2;
+    let left = (WIDTH as i32 - w) / 2;
+    let top = (HEIGHT as i32 - h) / 2;
+
+    for y in top..top + h {
+        for x in left..left + w {
+            blend_point(canvas, x, y, PANEL_COLOR, PANEL_ALPHA);
+        }
+    }
+
+    for (i, (key, action)) in rows.iter().enumerate() {
+        let x = left + PADDING + (i / per_column) as i32 * (column_width + SECTION_GAP);
+        let y = top + PADDING + (i % per_column) as i32 * line_height;
+        draw_text(canvas, x, y, key, KEY_COLOR);
+        draw_text(canvas, x + key_width + COLUMN_GAP, y, action, ACTION_COLOR);
+    }
+}
@@ -0,0 +1,135 @@
+use nalgebra::Point2;
+use std::time::{Duration, Instant};
+
+use crate::draw::{Canvas, draw_point};
+use crate::text::{GLYPH_HEIGHT, draw_text, text_width};
+use crate::{HEIGHT, WIDTH};
+
+const HUD_COLOR: u32 = 0xFFCCCCCC;
+const HUD_MARGIN: i32 = 6;
+// The FPS figure is averaged over this window so it is readable
+const FPS_SAMPLE_PERIOD: Duration = Duration::from_millis(500);
+const MESSAGE_DURATION: Duration = Duration::from_secs(2);
+const PROGRESS_WIDTH: i32 = 80;
+const PROGRESS_HEIGHT: i32 = 2;
+// Space between the step progress bar and the status line below it
+const PROGRESS_GAP: i32 = 4;
+const PROGRESS_TRACK_COLOR: u32 = 0xFF333333;
+
+pub struct FpsCounter {
+    frames: u32,
+    since: Instant,
+    fps: f32,
+}
+
+impl FpsCounter {
+    pub fn new() -> Self {
+        Self {
+            frames: 0,
+            since: Instant::now(),
+            fps: 0.0,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.frames += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed >= FPS_SAMPLE_PERIOD {
+            self.fps = self.frames as f32 / elapsed.as_secs_f32();
+            self.frames = 0;
+            self.since = Instant::now();
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas) {
+        let text = format!("FPS: {:.0}", self.fps);
+        let x = WIDTH as i32 - HUD_MARGIN - text_width(&text);
+        draw_text(canvas, x, HUD_MARGIN, &text, HUD_COLOR);
+    }
+}
+
+// A short notice shown at the bottom of the window for a couple of seconds
+pub struct StatusMessage {
+    text: String,
+    shown_at: Option<Instant>,
+}
+
+impl StatusMessage {
+    pub fn new() -> Self {
+        Self {
+            text: String::new(),
+            shown_at: None,
+        }
+    }
+
+    pub fn show(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.shown_at = Some(Instant::now());
+    }
+
+    pub fn is_showing(&self) -> bool {
+        self.shown_at
+            .is_some_and(|shown_at| shown_at.elapsed() < MESSAGE_DURATION)
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas) {
+        if self.is_showing() {
+            let y = HEIGHT as i32 - HUD_MARGIN - GLYPH_HEIGHT;
+            draw_text(canvas, HUD_MARGIN, y, &self.text, HUD_COLOR);
+        }
+    }
+}
+
+// The merge distance for near-duplicate points, under the FPS figure
+pub fn draw_merge_distance(canvas: &mut Canvas, epsilon: f32) {
+    let text = format!("merge < {} px", epsilon);
+    let x = WIDTH as i32 - HUD_MARGIN - text_width(&text);
+    draw_text(canvas, x, HUD_MARGIN * 2 + GLYPH_HEIGHT, &text, HUD_COLOR);
+}
+
+// Cursor position in window pixels and in curve coordinates, bottom right
+pub fn draw_cursor_position(canvas: &mut Canvas, screen: Point2<f32>, world: Point2<f32>) {
+    let text = format!(
+        "screen {:.0},{:.0}  world {:.1},{:.1}",
+        screen.x, screen.y, world.x, world.y
+    );
+    let x = WIDTH as i32 - HUD_MARGIN - text_width(&text);
+    let y = HEIGHT as i32 - HUD_MARGIN - GLYPH_HEIGHT;
+    draw_text(canvas, x, y, &text, HUD_COLOR);
+}
+
+// Thin bar above the status line filling up until the next animation step
+pub fn draw_step_progress(canvas: &mut Canvas, progress: f32) {
+    let y = HEIGHT as i32 - HUD_MARGIN - GLYPH_HEIGHT - PROGRESS_GAP - PROGRESS_HEIGHT;
+    let filled = (PROGRESS_WIDTH as f32 * progress.clamp(0.0, 1.0)) as i32;
+    for dy in 0..PROGRESS_HEIGHT {
+        for dx in 0..PROGRESS_WIDTH {
+            let color = if dx < filled { HUD_COLOR } else { PROGRESS_TRACK_COLOR };
+            draw_point(canvas, HUD_MARGIN + dx, y + dy, color);
+        }
+    }
+}
+
+// How far the shown step is from the limit curve, just above the step
+// progress bar, followed by `note` if there is one
+pub fn draw_step_error(canvas: &mut Canvas, step: usize, error: f32, note: Option<&str>) {
+    let mut text = format!("step {} error {:.2}", step, error);
+    if let Some(note) = note {
+        text.push_str(" - ");
+        text.push_str(note);
+    }
+    let y = HEIGHT as i32
+        - HUD_MARGIN
+        - GLYPH_HEIGHT
+        - PROGRESS_GAP * 2
+        - PROGRESS_HEIGHT
+        - GLYPH_HEIGHT;
+    draw_text(canvas, HUD_MARGIN, y, &text, HUD_COLOR);
+}
+
+// A standing note centred at the top of the window, shown for as long as the
+// caller keeps drawing it
+pub fn draw_notice(canvas: &mut Canvas, text: &str) {
+    let x = (WIDTH as i32 - text_width(text)) / 2;
+    draw_text(canvas, x, HUD_MARGIN, text, HUD_COLOR);
+}
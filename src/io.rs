@@ -0,0 +1,576 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use nalgebra::Point2;
+use serde::{Deserialize, Serialize};
+
+use crate::curve::{ChaikinCurve, ControlPoint};
+use crate::geometry::curve_length;
+
+/// Which polyline an export writes out.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportSource {
+    ControlPolygon,
+    SmoothedCurve,
+}
+
+// On-disk layout of a saved curve:
+// { "points": [ { "x": 10.0, "y": 20.0 }, ... ] }
+// Normalized files also carry the bounds the points were mapped from:
+// { "bounds": { "x": 120.0, "y": 80.0, "width": 300.0, "height": 200.0 }, ... }
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedCurve {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bounds: Option<Bounds>,
+    points: Vec<SavedPoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedPoint {
+    x: f32,
+    y: f32,
+}
+
+impl From<&Point2<f32>> for SavedPoint {
+    fn from(p: &Point2<f32>) -> Self {
+        Self { x: p.x, y: p.y }
+    }
+}
+
+// The bounding box of the control points that normalized coordinates are
+// relative to. Both axes are divided by its longer side, keeping the aspect
+// ratio, so the points land in [0, 1] x [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+struct Bounds {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+impl Bounds {
+    fn of(points: &[Point2<f32>]) -> Option<Self> {
+        let first = points.first()?;
+        let (mut min, mut max) = (*first, *first);
+        for p in points {
+            min = min.inf(p);
+            max = max.sup(p);
+        }
+        Some(Self {
+            x: min.x,
+            y: min.y,
+            width: max.x - min.x,
+            height: max.y - min.y,
+        })
+    }
+
+    // A single point, or several on top of each other, has no size to divide by
+    fn scale(&self) -> f32 {
+        let side = self.width.max(self.height);
+        if side > 0.0 { side } else { 1.0 }
+    }
+
+    fn normalize(&self, p: Point2<f32>) -> Point2<f32> {
+        Point2::new((p.x - self.x) / self.scale(), (p.y - self.y) / self.scale())
+    }
+
+    fn denormalize(&self, p: Point2<f32>) -> Point2<f32> {
+        Point2::new(self.x + p.x * self.scale(), self.y + p.y * self.scale())
+    }
+}
+
+// Every subdivision level, for inspecting convergence outside the app:
+// { "ratio": 0.25, "closed": false, "iterations": 7, "steps": [ [ {x, y}, ... ], ... ] }
+// with the same "bounds" as a saved curve when normalized.
+#[derive(Debug, Serialize)]
+struct SavedSteps {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bounds: Option<Bounds>,
+    ratio: f32,
+    closed: bool,
+    iterations: usize,
+    steps: Vec<Vec<SavedPoint>>,
+}
+
+impl ChaikinCurve {
+    // The bounds exports are normalized to, when `normalize_exports` is set
+    fn export_bounds(&self) -> Option<Bounds> {
+        if !self.normalize_exports {
+            return None;
+        }
+        let positions: Vec<Point2<f32>> =
+            self.control_points.iter().map(|cp| cp.position).collect();
+        Bounds::of(&positions)
+    }
+
+    /// Writes the control points to `path` as JSON.
+    pub fn save_points(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let bounds = self.export_bounds();
+        let saved = SavedCurve {
+            bounds,
+            points: self
+                .control_points
+                .iter()
+                .map(|cp| SavedPoint::from(&export_point(bounds, cp.position)))
+                .collect(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&saved)?)
+    }
+
+    /// Replaces the control points with the ones stored in `path`, mapping
+    /// normalized points back to the bounds saved with them.
+    pub fn load_points(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let saved: SavedCurve = serde_json::from_str(&fs::read_to_string(path)?)?;
+        self.control_points = saved
+            .points
+            .iter()
+            .map(|p| {
+                let p = import_point(saved.bounds, Point2::new(p.x, p.y));
+                ControlPoint::new(p.x, p.y)
+            })
+            .collect();
+        self.segment_ratios.clear();
+        self.reset_animation();
+        Ok(())
+    }
+
+    /// Writes one `step_index,point_count,arc_length` row per subdivision
+    /// level to `path` as CSV, starting with the control polygon as step 0,
+    /// to show how both converge. Lengths are in curve coordinates, even
+    /// when exports are normalized. Takes its levels the same way as
+    /// `export_steps_json`.
+    pub fn export_steps_csv(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut steps = self.animation_steps.clone();
+        if steps.is_empty() {
+            steps = self.subdivision_levels(None);
+        }
+
+        let mut csv = String::from("step_index,point_count,arc_length\n");
+        for (index, level) in steps.iter().enumerate() {
+            let length = curve_length(level, self.is_closed());
+            csv.push_str(&format!("{},{},{}\n", index, level.len(), length));
+        }
+        fs::write(path, csv)
+    }
+
+    /// Writes every subdivision level, starting with the control polygon, to
+    /// `path` as JSON along with the ratio and closed flag that produced them.
+    /// Uses the generated animation steps if there are any, otherwise
+    /// computes the levels up to `max_steps`.
+    pub fn export_steps_json(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut steps = self.animation_steps.clone();
+        if steps.is_empty() {
+            steps = self.subdivision_levels(None);
+        }
+
+        let bounds = self.export_bounds();
+        let saved = SavedSteps {
+            bounds,
+            ratio: self.ratio,
+            closed: self.is_closed(),
+            iterations: steps.len() - 1,
+            steps: steps
+                .iter()
+                .map(|level| {
+                    level.iter().map(|&p| SavedPoint::from(&export_point(bounds, p))).collect()
+                })
+                .collect(),
+        };
+        fs::write(path, serde_json::to_string_pretty(&saved)?)
+    }
+}
+
+impl ChaikinCurve {
+    fn export_points(&self, source: ExportSource) -> Vec<Point2<f32>> {
+        match source {
+            ExportSource::ControlPolygon => {
+                self.control_points.iter().map(|cp| cp.position).collect()
+            }
+            ExportSource::SmoothedCurve => self.final_curve(),
+        }
+    }
+
+    /// Writes the curve as a Wavefront OBJ: one `v x y 0` line per point and a
+    /// single `l` element joining them in order (and back to the start for a
+    /// closed curve). Normalized exports record their bounds in a
+    /// `# bounds x y width height` comment.
+    pub fn export_obj(&self, path: impl AsRef<Path>, source: ExportSource) -> io::Result<()> {
+        let points = self.export_points(source);
+        let bounds = self.export_bounds();
+        let mut obj = String::from("# Chaikin curve\n");
+        if let Some(b) = bounds {
+            obj.push_str(&format!("# bounds {} {} {} {}\n", b.x, b.y, b.width, b.height));
+        }
+        for &p in &points {
+            let p = export_point(bounds, p);
+            obj.push_str(&format!("v {} {} 0\n", p.x, p.y));
+        }
+        if points.len() >= 2 {
+            let mut indices: Vec<String> = (1..=points.len()).map(|i| i.to_string()).collect();
+            if self.is_closed() {
+                // A closed loop ends back on its first vertex
+                indices.push("1".to_string());
+            }
+            obj.push_str(&format!("l {}\n", indices.join(" ")));
+        }
+        fs::write(path, obj)
+    }
+
+    /// Replaces the control points with the vertices of an OBJ file, ignoring
+    /// z. If the file has an `l` element its vertex order is used (and a loop
+    /// back to its first vertex makes the curve closed), otherwise the
+    /// vertices are taken in file order. A `# bounds` comment maps
+    /// normalized vertices back to the size they were exported at.
+    pub fn import_obj(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let source = fs::read_to_string(path)?;
+        let mut vertices: Vec<Point2<f32>> = Vec::new();
+        let mut polyline: Option<Vec<usize>> = None;
+        let mut bounds: Option<Bounds> = None;
+
+        for (number, line) in source.lines().enumerate() {
+            let invalid = |what: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", number + 1, what),
+                )
+            };
+            // A stray \r from CRLF endings is whitespace, so it never ends up in a field
+            let mut fields = line.split_whitespace();
+            match fields.next() {
+                Some("v") => {
+                    let mut coord = || -> io::Result<f32> {
+                        fields
+                            .next()
+                            .and_then(|f| f.parse().ok())
+                            .ok_or_else(|| invalid("expected 'v x y [z]'"))
+                    };
+                    let x = coord()?;
+                    let y = coord()?;
+                    vertices.push(Point2::new(x, y));
+                }
+                Some("l") if polyline.is_none() => {
+                    let mut indices = Vec::new();
+                    for field in fields {
+                        // Either `i` or `i/vt`; negative indices count back from the end
+                        let index: i64 = field
+                            .split('/')
+                            .next()
+                            .and_then(|i| i.parse().ok())
+                            .ok_or_else(|| invalid("bad vertex index"))?;
+                        let resolved = if index < 0 {
+                            vertices.len() as i64 + index
+                        } else {
+                            index - 1
+                        };
+                        if resolved < 0 {
+                            return Err(invalid("vertex index out of range"));
+                        }
+                        indices.push(resolved as usize);
+                    }
+                    polyline = Some(indices);
+                }
+                Some("#") if line.split_whitespace().nth(1) == Some("bounds") => {
+                    let values: Vec<f32> =
+                        fields.skip(1).filter_map(|f| f.parse().ok()).collect();
+                    let [x, y, width, height] = values[..] else {
+                        return Err(invalid("expected '# bounds x y width height'"));
+                    };
+                    bounds = Some(Bounds { x, y, width, height });
+                }
+                // Comments, blank lines and anything other than geometry
+                _ => {}
+            }
+        }
+
+        // A polyline that returns to its first vertex describes a closed curve
+        let mut closed = false;
+        if let Some(indices) = polyline.as_mut()
+            && indices.len() > 3
+            && indices.first() == indices.last()
+        {
+            indices.pop();
+            closed = true;
+        }
+
+        let points = match polyline {
+            Some(indices) => indices
+                .iter()
+                .map(|&i| {
+                    vertices.get(i).copied().ok_or_else(|| {
+                        io::Error::new(io::ErrorKind::InvalidData, "vertex index out of range")
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()?,
+            None => vertices,
+        };
+
+        self.control_points = points
+            .iter()
+            .map(|&p| {
+                let p = import_point(bounds, p);
+                ControlPoint::new(p.x, p.y)
+            })
+            .collect();
+        self.segment_ratios.clear();
+        self.closed = closed;
+        self.reset_animation();
+        Ok(())
+    }
+}
+
+// A point as written out, normalized to `bounds` if there are any
+fn export_point(bounds: Option<Bounds>, p: Point2<f32>) -> Point2<f32> {
+    bounds.map_or(p, |b| b.normalize(p))
+}
+
+// A point as read back in, mapped from normalized coordinates if the file
+// says what they were normalized to
+fn import_point(bounds: Option<Bounds>, p: Point2<f32>) -> Point2<f32> {
+    bounds.map_or(p, |b| b.denormalize(p))
+}
+
+impl ChaikinCurve {
+    /// Replaces the control points with the vertices of the first `<path>`
+    /// element's `d` attribute in an SVG file. `M`, `L`, `H`, `V` and `Z` are
+    /// followed exactly, absolute or relative, and a `Z` makes the curve
+    /// closed. Curve commands (`C`, `S`, `Q`, `T`, `A`) are replaced by a
+    /// straight line to their end point; the ones met are returned so they
+    /// can be reported. Only the first subpath is read.
+    pub fn import_svg(&mut self, path: impl AsRef<Path>) -> io::Result<Vec<char>> {
+        let source = fs::read_to_string(path)?;
+        let data = path_data(&source)
+            .ok_or_else(|| invalid_svg("no <path> element with a d attribute"))?;
+        let (points, closed, approximated) = parse_path_data(data)?;
+        if points.is_empty() {
+            return Err(invalid_svg("the path has no points"));
+        }
+
+        self.control_points = points.iter().map(|p| ControlPoint::new(p.x, p.y)).collect();
+        self.segment_ratios.clear();
+        self.closed = closed;
+        self.reset_animation();
+        Ok(approximated)
+    }
+}
+
+fn invalid_svg(what: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, what.to_string())
+}
+
+// The value of the `d` attribute of the first `<path` element
+fn path_data(svg: &str) -> Option<&str> {
+    let element = &svg[svg.find("<path")?..];
+    let element = &element[..element.find('>')?];
+    // The attribute may be anywhere in the tag, but shouldn't be confused
+    // with the end of another name such as `id`
+    let mut rest = element;
+    loop {
+        let at = rest.find("d=")?;
+        let before = rest[..at].chars().next_back();
+        rest = &rest[at + 2..];
+        if before.is_some_and(|c| c.is_whitespace()) {
+            break;
+        }
+    }
+    let quote = rest.chars().next().filter(|&c| c == '"' || c == '\'')?;
+    let value = &rest[1..];
+    Some(&value[..value.find(quote)?])
+}
+
+// Follows the commands of SVG path data, returning the vertices, whether
+// the path was closed and the curve commands drawn as straight lines
+fn parse_path_data(data: &str) -> io::Result<(Vec<Point2<f32>>, bool, Vec<char>)> {
+    let mut points: Vec<Point2<f32>> = Vec::new();
+    let mut approximated: Vec<char> = Vec::new();
+    let mut closed = false;
+    let mut current = Point2::origin();
+    let mut tokens = PathTokens { rest: data };
+    let mut command: Option<char> = None;
+
+    while let Some(token) = tokens.peek() {
+        let letter = match token {
+            PathToken::Command(letter) => {
+                tokens.next();
+                letter
+            }
+            // Numbers after a command's own repeat it, except that extra
+            // pairs after a move are lines
+            PathToken::Number(_) => match command {
+                Some('M') => 'L',
+                Some('m') => 'l',
+                Some(letter) if !matches!(letter, 'Z' | 'z') => letter,
+                _ => return Err(invalid_svg("path data must start with a command")),
+            },
+        };
+        if closed || (matches!(letter, 'M' | 'm') && !points.is_empty()) {
+            // A second subpath starts; only the first is read
+            break;
+        }
+        command = Some(letter);
+
+        let relative = letter.is_ascii_lowercase();
+        let offset = if relative { current.coords } else { Default::default() };
+        let mut number = || {
+            tokens
+                .number()
+                .ok_or_else(|| invalid_svg(&format!("'{}' is missing a number", letter)))
+        };
+        current = match letter.to_ascii_uppercase() {
+            'M' | 'L' => Point2::new(number()?, number()?) + offset,
+            'H' => Point2::new(number()? + offset.x, current.y),
+            'V' => Point2::new(current.x, number()? + offset.y),
+            'Z' => {
+                closed = true;
+                continue;
+            }
+            curve @ ('C' | 'S' | 'Q' | 'T' | 'A') => {
+                // Skip the control points (or arc shape) and keep the end
+                let skipped = match curve {
+                    'C' => 4,
+                    'S' | 'Q' => 2,
+                    'T' => 0,
+                    _ => 5,
+                };
+                for _ in 0..skipped {
+                    number()?;
+                }
+                if !approximated.contains(&curve) {
+                    approximated.push(curve);
+                }
+                Point2::new(number()?, number()?) + offset
+            }
+            other => return Err(invalid_svg(&format!("unknown path command '{}'", other))),
+        };
+        points.push(current);
+    }
+
+    // A closing Z usually returns to a point that is already the first one
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    Ok((points, closed, approximated))
+}
+
+enum PathToken {
+    Command(char),
+    Number(f32),
+}
+
+// Splits path data into commands and numbers. Numbers needn't be separated
+// when the next one starts with a sign or a second decimal point, as in
+// "10-5" or "0.5.5".
+struct PathTokens<'a> {
+    rest: &'a str,
+}
+
+impl PathTokens<'_> {
+    fn peek(&mut self) -> Option<PathToken> {
+        self.rest = self.rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        let c = self.rest.chars().next()?;
+        if c.is_ascii_alphabetic() {
+            return Some(PathToken::Command(c));
+        }
+        let length = number_length(self.rest);
+        // Anything else that isn't a number is reported as a command
+        match self.rest[..length].parse() {
+            Ok(number) => Some(PathToken::Number(number)),
+            Err(_) => Some(PathToken::Command(c)),
+        }
+    }
+
+    fn next(&mut self) -> Option<PathToken> {
+        let token = self.peek()?;
+        let length = match token {
+            PathToken::Command(c) => c.len_utf8(),
+            PathToken::Number(_) => number_length(self.rest),
+        };
+        self.rest = &self.rest[length..];
+        Some(token)
+    }
+
+    fn number(&mut self) -> Option<f32> {
+        match self.peek()? {
+            PathToken::Number(_) => match self.next() {
+                Some(PathToken::Number(n)) => Some(n),
+                _ => None,
+            },
+            PathToken::Command(_) => None,
+        }
+    }
+}
+
+// Length of the number at the start of `text`: sign, digits with at most one
+// decimal point, then an optional exponent
+fn number_length(text: &str) -> usize {
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    if matches!(bytes.first(), Some(b'+' | b'-')) {
+        i += 1;
+    }
+    let mut seen_dot = false;
+    while let Some(&b) = bytes.get(i) {
+        match b {
+            b'0'..=b'9' => {}
+            b'.' if !seen_dot => seen_dot = true,
+            _ => break,
+        }
+        i += 1;
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(bytes.get(j), Some(b'+' | b'-')) {
+            j += 1;
+        }
+        if bytes.get(j).is_some_and(|b| b.is_ascii_digit()) {
+            while bytes.get(j).is_some_and(|b| b.is_ascii_digit()) {
+                j += 1;
+            }
+            i = j;
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalized_points_round_trip_to_their_bounds() {
+        let points: Vec<_> = [(120.0, 80.0), (420.0, 130.0), (300.0, 280.0)]
+            .iter()
+            .map(|&(x, y)| Point2::new(x, y))
+            .collect();
+        let bounds = Bounds::of(&points).expect("there are points");
+        for &p in &points {
+            let n = bounds.normalize(p);
+            // The longer side spans [0, 1] and the other keeps its proportion
+            assert!((0.0..=1.0).contains(&n.x) && (0.0..=2.0 / 3.0).contains(&n.y));
+            assert!((bounds.denormalize(n) - p).norm() < 1e-3);
+        }
+        assert_eq!(bounds.normalize(points[0]), Point2::origin());
+        assert_eq!(bounds.normalize(points[1]).x, 1.0);
+    }
+
+    #[test]
+    fn normalized_save_loads_back_at_the_original_size() {
+        let mut curve = ChaikinCurve::new();
+        curve.add_point(120.0, 80.0);
+        curve.add_point(420.0, 130.0);
+        curve.add_point(300.0, 280.0);
+        curve.normalize_exports = true;
+        let name = format!("chaikin-normalized-{}.json", std::process::id());
+        let path = std::env::temp_dir().join(name);
+        curve.save_points(&path).expect("saved");
+        let mut loaded = ChaikinCurve::new();
+        let result = loaded.load_points(&path);
+        let _ = fs::remove_file(&path);
+        result.expect("loaded");
+
+        assert_eq!(loaded.control_points.len(), 3);
+        for (a, b) in loaded.control_points.iter().zip(&curve.control_points) {
+            assert!((a.position - b.position).norm() < 1e-3);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use nalgebra::{Point2, Vector2};
+
+use crate::draw::{Canvas, draw_line};
+use crate::text::{GLYPH_HEIGHT, draw_text, text_width};
+
+// Distance from a point to its label when nothing is crowding it
+const LABEL_OFFSET: f32 = 10.0;
+// Points closer than this are treated as one cluster
+const CLUSTER_RADIUS: f32 = 24.0;
+// How far a colliding label is pushed outward per attempt
+const PUSH_STEP: f32 = 4.0;
+const MAX_PUSHES: usize = 24;
+// Empty space kept between neighbouring labels
+const LABEL_PADDING: i32 = 2;
+
+const LABEL_COLOR: u32 = 0xFFCCCCCC;
+const LEADER_COLOR: u32 = 0xFF666666;
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    x: i32,
+    y: i32,
+    w: i32,
+    h: i32,
+}
+
+impl Rect {
+    fn centered_at(center: Point2<f32>, w: i32, h: i32) -> Self {
+        Self {
+            x: center.x.round() as i32 - w / 2,
+            y: center.y.round() as i32 - h / 2,
+            w,
+            h,
+        }
+    }
+
+    fn overlaps(&self, other: &Rect) -> bool {
+        self.x < other.x + other.w + LABEL_PADDING
+            && other.x < self.x + self.w + LABEL_PADDING
+            && self.y < other.y + other.h + LABEL_PADDING
+            && other.y < self.y + self.h + LABEL_PADDING
+    }
+
+    // Closest point on the rectangle to `p`, used as the leader line end
+    fn nearest_to(&self, p: Point2<f32>) -> (i32, i32) {
+        let x = (p.x.round() as i32).clamp(self.x, self.x + self.w - 1);
+        let y = (p.y.round() as i32).clamp(self.y, self.y + self.h - 1);
+        (x, y)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    text: String,
+    anchor: Point2<f32>,
+    rect: Rect,
+    // Whether the label was moved away from its default spot
+    displaced: bool,
+}
+
+// Places an index label next to every point. Labels of crowded points are
+// pushed radially away from the centroid of their cluster, then nudged further
+// out until they stop overlapping labels placed before them. This is a cheap
+// greedy heuristic, not an optimal layout.
+pub fn layout_labels(points: &[Point2<f32>]) -> Vec<Label> {
+    let mut labels: Vec<Label> = Vec::with_capacity(points.len());
+
+    for (i, &p) in points.iter().enumerate() {
+        let text = i.to_string();
+        let w = text_width(&text);
+
+        let neighbours: Vec<Point2<f32>> = points
+            .iter()
+            .enumerate()
+            .filter(|&(j, q)| j != i && (q - p).norm() < CLUSTER_RADIUS)
+            .map(|(_, q)| *q)
+            .collect();
+
+        let direction = if neighbours.is_empty() {
+            // Up and to the right, out of the way of the point itself
+            Vector2::new(1.0, -1.0).normalize()
+        } else {
+            let sum = neighbours
+                .iter()
+                .fold(p.coords, |acc, q| acc + q.coords);
+            let centroid = sum / (neighbours.len() + 1) as f32;
+            let away = p.coords - centroid;
+            if away.norm() > f32::EPSILON {
+                away.normalize()
+            } else {
+                // Coincident points: fan them out by index
+                let angle = i as f32 * 2.399_963; // golden angle
+                Vector2::new(angle.cos(), angle.sin())
+            }
+        };
+
+        let mut distance = if neighbours.is_empty() {
+            LABEL_OFFSET
+        } else {
+            LABEL_OFFSET + CLUSTER_RADIUS / 2.0
+        };
+        let mut rect = Rect::centered_at(p + direction * distance, w, GLYPH_HEIGHT);
+
+        for _ in 0..MAX_PUSHES {
+            if !labels.iter().any(|l| l.rect.overlaps(&rect)) {
+                break;
+            }
+            distance += PUSH_STEP;
+            rect = Rect::centered_at(p + direction * distance, w, GLYPH_HEIGHT);
+        }
+
+        labels.push(Label {
+            text,
+            anchor: p,
+            rect,
+            displaced: distance > LABEL_OFFSET,
+        });
+    }
+
+    labels
+}
+
+pub fn draw_labels(canvas: &mut Canvas, labels: &[Label]) {
+    for label in labels {
+        if label.displaced {
+            let (x, y) = label.rect.nearest_to(label.anchor);
+            draw_line(
+                canvas,
+                label.anchor.x.round() as i32,
+                label.anchor.y.round() as i32,
+                x,
+                y,
+                LEADER_COLOR,
+            );
+        }
+        draw_text(canvas, label.rect.x, label.rect.y, &label.text, LABEL_COLOR);
+    }
+}
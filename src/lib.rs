@@ -0,0 +1,13 @@
+mod clock;
+mod curve;
+pub mod geometry;
+mod io;
+pub mod render;
+
+pub use clock::{Clock, SystemClock};
+pub use curve::{
+    ANIMATION_STEP_DURATION, ChaikinCurve, ControlPoint, DEFAULT_RATIO, MAX_ANIMATION_STEPS,
+    MirrorAxis, POINT_RADIUS, STEP_LIMIT, StepCallback, TRACE_STEP_DURATION, chaikin_all_levels,
+    chaikin_final, chaikin_level,
+};
+pub use io::ExportSource;
@@ -1,337 +1,1473 @@
-use minifb::{Key, MouseButton, MouseMode, Scale, Window, WindowOptions};
-use nalgebra::{Point2};
-use std::time::{Duration, Instant};
+use chaikin::geometry::{
+    are_collinear, curvature_at, resample_by_arc_length, ring_points, tangent_at,
+};
+use chaikin::{ChaikinCurve, DEFAULT_RATIO, ExportSource, POINT_RADIUS};
+use minifb::{Key, KeyRepeat, MouseButton, MouseMode, Scale, Window, WindowOptions};
+use nalgebra::Point2;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+mod actions;
+mod autosave;
+mod background;
+mod bindings;
+mod cli;
+mod coord_entry;
+mod draw;
+mod effects;
+mod gallery;
+mod help;
+mod hud;
+mod labels;
+mod minimap;
+mod nudge;
+mod point_list;
+mod pressure;
+mod random;
+mod screenshot;
+mod session;
+mod text;
+mod viewport;
+
+use actions::{Action, RING_POINTS, apply_action};
+use autosave::{AutoSave, RECOVERY_FILE};
+use background::Background;
+use bindings::{BINDINGS_FILE, load_bindings};
+use cli::Args;
+use help::draw_help;
+use hud::{
+    FpsCounter, StatusMessage, draw_cursor_position, draw_merge_distance, draw_notice,
+    draw_step_error, draw_step_progress,
+};
+
+use coord_entry::{CoordEntry, EntryEvent};
+use draw::{
+    Canvas, MarkerShape, blend_color, downsample, draw_arrow, draw_circle, draw_dashed_polyline,
+    draw_glow, draw_line, draw_marker, draw_marker_aa, draw_polyline, draw_polyline_aa, draw_ring,
+    draw_ring_aa, draw_segment, hue_color,
+};
+use effects::PointEffects;
+use gallery::Gallery;
+use labels::{draw_labels, layout_labels};
+use minimap::Minimap;
+use nudge::KeyRepeater;
+use point_list::PointList;
+use pressure::WeightSource;
+use random::random_blob;
+use screenshot::{screenshot, screenshot_path};
+use session::{Player, Recorder, SESSION_FILE};
+use text::{GLYPH_HEIGHT, draw_text, text_width};
+use viewport::Viewport;
+
+const WINDOW_TITLE: &str = "Chaikin Curve Animation";
 const WIDTH: usize = 800;
 const HEIGHT: usize = 600;
-const POINT_RADIUS: i32 = 5;
-const ANIMATION_STEP_DURATION: Duration = Duration::from_millis(500);
-const MAX_ANIMATION_STEPS: usize = 7;
+const FRAME_TIME: Duration = Duration::from_micros(16600);
+// Half the length of the tangent indicator drawn through each control point
+const TANGENT_LENGTH: f32 = 20.0;
+const TANGENT_COLOR: u32 = 0xFF00AAFF;
+const MOTION_COLOR: u32 = 0xFFFF60C0;
+// How bright the limit curve is drawn, as a fraction of the curve color
+const LIMIT_BRIGHTNESS: f32 = 0.35;
+// Likewise for the ghost of the curve from before the current drag
+const GHOST_BRIGHTNESS: f32 = 0.3;
+const MOTION_ARROW_HEAD: f32 = 4.0;
+const HULL_COLOR: u32 = 0xFF808080;
+const SEGMENT_LENGTH_COLOR: u32 = 0xFFC0C0C0;
+// Crosshair marking the centroid while the points are rotated or scaled,
+// kept up this long after the last step so holding a key doesn't flicker
+const CENTROID_COLOR: u32 = 0xFFFFFF80;
+const CENTROID_MARKER_SIZE: i32 = 6;
+const CENTROID_MARKER_LINGER: Duration = Duration::from_millis(600);
+// Length of each dash (and gap) in the convex hull outline, in pixels
+const HULL_DASH: f32 = 6.0;
+// Dash length of the marching ants curve, and how far the dashes move along
+// it each frame
+const CURVE_DASH: f32 = 8.0;
+// Overlay opacity change per key press, in percent
+const OVERLAY_OPACITY_STEP: u32 = 10;
+const MARCH_STEP: f32 = 0.5;
+const GRID_SIZE: f32 = 20.0;
+const GRID_COLOR: u32 = 0xFF202020;
+// Grid lines closer together than this on screen are not drawn
+const MIN_GRID_SPACING: f32 = 4.0;
+// Zoom change per scroll wheel notch
+const ZOOM_STEP: f32 = 1.1;
+// Time constant of the eased view changes, in seconds: they are all but
+// finished after three of these
+const VIEW_EASE_TIME: f32 = 0.05;
+// How far in from the window's edges Home leaves the points it brings into view
+const FIT_MARGIN: f32 = 20.0;
+// While sketching, a new point is dropped once the cursor is this many
+// screen pixels from the last one
+const MIN_POINT_SPACING: f32 = 8.0;
+// A ring dragged out smaller than this (in screen pixels) is dropped
+const MIN_RING_RADIUS: f32 = 10.0;
+const RAW_CLICK_COLOR: u32 = 0xFF806020;
+// Ratios (and their colours) overlaid by the ratio comparison view
+const COMPARED_RATIOS: [(f32, u32); 3] = [
+    (0.15, 0xFFFF8040),
+    (0.25, 0xFF40FF40),
+    (0.35, 0xFF40A0FF),
+];
+const OBJ_FILE: &str = "chaikin.obj";
+const SVG_FILE: &str = "chaikin.svg";
+// Where F3 stores the points referenced by the printed command line
+const SNAPSHOT_FILE: &str = "chaikin_points.json";
+const STEPS_FILE: &str = "chaikin_steps.json";
+const STEPS_CSV_FILE: &str = "chaikin_steps.csv";
+// Brightness range of the earlier levels in the all-steps overlay
+const OLDEST_STEP_BRIGHTNESS: f32 = 0.15;
+const NEWEST_STEP_BRIGHTNESS: f32 = 0.6;
+// Colors the curve can be drawn in, starting with the original green
+const CURVE_PALETTE: [u32; 6] = [
+    0xFF00FF00, 0xFF00E0FF, 0xFFFF9030, 0xFFFF50E0, 0xFFFFFF40, 0xFFE0E0E0,
+];
+const GLOW_RADIUS: f32 = 5.0;
+const GLOW_ALPHA: f32 = 0.35;
+// Points within this many pixels of a straight line count as collinear
+const COLLINEAR_EPSILON: f32 = 0.5;
+// Distance between the points of the curve with even spacing on, in pixels
+const EVEN_SPACING: f32 = 6.0;
+const CURVE_DOT_RADIUS: i32 = 2;
+const POINT_COLOR: u32 = 0xFFFFFFFF;
+const SELECTED_COLOR: u32 = 0xFFFF0000;
+// Gap between a selected point and its selection ring
+const SELECTION_RING_GAP: i32 = 3;
+// Locked points get a square outline this far outside their disc
+const LOCK_MARKER_GAP: i32 = 2;
+const LOCK_COLOR: u32 = 0xFFFFC040;
+// Cusps get a diamond outline this far outside the marker
+const CUSP_MARKER_GAP: i32 = 3;
+const CUSP_COLOR: u32 = 0xFF40E0FF;
+// Curvature at which the curvature view is fully red (a 10 px radius bend)
+const MAX_DISPLAY_CURVATURE: f32 = 0.1;
+const STRAIGHT_COLOR: u32 = 0xFF3070FF;
+const SHARP_COLOR: u32 = 0xFFFF3030;
+// How far round the colour wheel the rainbow curve goes from its start to
+// its end, stopping short of red again so the two ends look different
+const RAINBOW_HUES: f32 = 300.0;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-struct ControlPoint {
-    position: Point2<f32>,
-    selected: bool,
+enum RenderMode {
+    Lines,
+    // Every computed point as its own dot, which also shows how dense each
+    // subdivision level is
+    Points,
+    // Segments coloured from blue (straight) to red (tight bends)
+    Curvature,
+    // The hue changing along the curve's length, so overlapping stretches
+    // of a dense curve can be told apart
+    Rainbow,
 }
 
-impl ControlPoint {
-    fn new(x: f32, y: f32) -> Self {
-        Self {
-            position: Point2::new(x, y),
-            selected: false,
+impl RenderMode {
+    fn next(self) -> Self {
+        match self {
+            RenderMode::Lines => RenderMode::Points,
+            RenderMode::Points => RenderMode::Curvature,
+            RenderMode::Curvature => RenderMode::Rainbow,
+            RenderMode::Rainbow => RenderMode::Lines,
         }
     }
 }
 
-struct ChaikinCurve {
-    control_points: Vec<ControlPoint>,
-    animation_steps: Vec<Vec<Point2<f32>>>,
-    current_step: usize,
-    animating: bool,
-    last_step_time: Instant,
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SelectionStyle {
+    FilledRed,
+    // An outline around the point, leaving its own colour visible
+    Ring,
 }
 
-impl ChaikinCurve {
-    fn new() -> Self {
-        Self {
-            control_points: Vec::new(),
-            animation_steps: Vec::new(),
-            current_step: 0,
-            animating: false,
-            last_step_time: Instant::now(),
-        }
+// Repeats the first point at the end so a closed curve draws its closing segment
+fn close_loop(points: &[Point2<f32>], closed: bool) -> Vec<Point2<f32>> {
+    let mut outline = points.to_vec();
+    if closed && let Some(&first) = points.first() {
+        outline.push(first);
     }
+    outline
+}
 
-    fn add_point(&mut self, x: f32, y: f32) {
-        self.control_points.push(ControlPoint::new(x, y));
-    }
+// Cursor position in curve coordinates
+fn world_mouse_pos(window: &Window, mode: MouseMode, viewport: &Viewport) -> Option<(f32, f32)> {
+    let (x, y) = window.get_mouse_pos(mode)?;
+    let world = viewport.screen_to_world(Point2::new(x, y));
+    Some((world.x, world.y))
+}
 
-    fn clear_points(&mut self) {
-        self.control_points.clear();
-        self.reset_animation();
-    }
+fn shift_held(window: &Window) -> bool {
+    window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift)
+}
 
-    fn reset_animation(&mut self) {
-        self.animation_steps.clear();
-        self.current_step = 0;
-        self.animating = false;
-    }
+fn ctrl_held(window: &Window) -> bool {
+    window.is_key_down(Key::LeftCtrl) || window.is_key_down(Key::RightCtrl)
+}
 
-    fn start_animation(&mut self) {
-        if self.control_points.len() <= 1 {
-            return; // Need at least 2 points to draw a curve
-        }
+fn alt_held(window: &Window) -> bool {
+    window.is_key_down(Key::LeftAlt) || window.is_key_down(Key::RightAlt)
+}
 
-        self.reset_animation();
-        self.generate_animation_steps();
-        self.animating = true;
-        self.last_step_time = Instant::now();
+// Builds the starting curve from the command line: recovered or loaded
+// points plus the initial settings
+fn curve_from_args(args: &Args) -> ChaikinCurve {
+    let mut chaikin = ChaikinCurve::new();
+    if args.recover {
+        match chaikin.load_points(RECOVERY_FILE) {
+            Ok(()) => println!(
+                "Restored {} points from {}",
+                chaikin.control_points.len(),
+                RECOVERY_FILE
+            ),
+            Err(err) => eprintln!("Could not restore {}: {}", RECOVERY_FILE, err),
+        }
+    } else if AutoSave::recovery_available() {
+        println!(
+            "Found {} from an earlier session; run with --recover to restore it before editing",
+            RECOVERY_FILE
+        );
     }
+    if let Some(input) = &args.input
+        && let Err(err) = chaikin.load_points(input)
+    {
+        eprintln!("Could not load {}: {}", input.display(), err);
+        std::process::exit(1);
+    }
+    if let Some(iterations) = args.iterations {
+        chaikin.max_steps = iterations;
+    }
+    chaikin.max_control_points = args.max_points;
+    if args.closed {
+        chaikin.closed = true;
+    }
+    chaikin.normalize_exports = args.normalize;
+    chaikin.jitter = args.jitter.unwrap_or(0.0);
+    chaikin.jitter_seed = args.jitter_seed.unwrap_or(0);
+    if let Some(ratio) = args.ratio {
+        chaikin.ratio = ratio;
+    }
+    if let Some(count) = args.random {
+        // Printed so a blob worth keeping can be made again
+        let seed = args.seed.unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        });
+        println!("Random blob of {} points from --seed {}", count, seed);
+        for point in random_blob(count, seed) {
+            chaikin.add_point(point.x, point.y);
+        }
+        chaikin.closed = true;
+        if !args.replay {
+            chaikin.start_animation();
+        }
+    }
+    // Same as pressing F5 once the points are in
+    if args.replay {
+        chaikin.start_trace();
+    }
+    chaikin
+}
 
-    fn generate_animation_steps(&mut self) {
-        self.animation_steps.clear();
+// Times generating every step `runs` times over and prints the spread, for
+// checking the algorithm's speed where there is no display
+fn profile(chaikin: &mut ChaikinCurve, runs: usize) {
+    if chaikin.control_points.len() < 2 {
+        eprintln!("--profile needs at least 2 points; load some with --input or --random");
+        std::process::exit(1);
+    }
+    let mut timings: Vec<Duration> = (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            chaikin.generate_animation_steps();
+            start.elapsed()
+        })
+        .collect();
+    timings.sort();
 
-        // Initial step is just the control points
-        let initial_points: Vec<Point2<f32>> = self.control_points
-            .iter()
-            .map(|cp| cp.position)
-            .collect();
-        
-        self.animation_steps.push(initial_points.clone());
+    let points = chaikin.animation_steps.last().map_or(0, |step| step.len());
+    println!(
+        "{} control points, {} steps, {} points at the last step",
+        chaikin.control_points.len(),
+        chaikin.animation_steps.len().saturating_sub(1),
+        points
+    );
+    println!(
+        "{} runs: min {:?}  median {:?}  max {:?}",
+        runs,
+        timings[0],
+        timings[runs / 2],
+        timings[runs - 1]
+    );
+}
 
-        let mut current_points = initial_points;
-        
-        // Generate the subsequent steps
-        for _ in 0..MAX_ANIMATION_STEPS {
-            current_points = self.chaikin_step(&current_points);
-            self.animation_steps.push(current_points.clone());
+fn main() {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
         }
+    };
+
+    // Everything from the command line is applied before a window exists
+    let mut chaikin = curve_from_args(&args);
+    // The points just as `--input` gave them, to tell whether that file still
+    // holds them
+    let input_points: Option<Vec<Point2<f32>>> = (args.input.is_some() && args.random.is_none())
+        .then(|| chaikin.control_points.iter().map(|cp| cp.position).collect());
+    if let Some(runs) = args.profile {
+        profile(&mut chaikin, runs);
+        return;
     }
+    let bindings = load_bindings(BINDINGS_FILE);
+    let background = args.trace.as_ref().map(|path| {
+        match Background::load(path, WIDTH, HEIGHT) {
+            Ok(background) => background,
+            Err(err) => {
+                eprintln!("Could not load {}: {}", path.display(), err);
+                std::process::exit(1);
+            }
+        }
+    });
 
-    fn chaikin_step(&self, points: &[Point2<f32>]) -> Vec<Point2<f32>> {
-        if points.len() <= 2 {
-            return points.to_vec();
+    let mut buffer: Vec<u32> = vec![0; WIDTH * HEIGHT];
+    
+    let mut window = match Window::new(
+        WINDOW_TITLE,
+        WIDTH,
+        HEIGHT,
+        WindowOptions {
+            scale: Scale::X1,
+            ..WindowOptions::default()
+        },
+    ) {
+        Ok(window) => window,
+        Err(err) => {
+            eprintln!("Could not open a window ({}); a graphical display is required", err);
+            std::process::exit(1);
         }
+    };
+
+    // We don't want to tear now
+    let mut rate_limited = true;
+    window.limit_update_rate(Some(FRAME_TIME));
+    let mut fps = FpsCounter::new();
+    let mut status = StatusMessage::new();
 
-        let mut result = Vec::new();
+    let mut autosave = AutoSave::new(&chaikin);
+    let mut dragging = false;
+    // Where the current drag started, and how far the selection has been
+    // moved from there so far
+    let mut drag_origin = (0.0, 0.0);
+    // The smoothed curve as it was when the current drag started
+    let mut drag_ghost: Option<Vec<Point2<f32>>> = None;
+    let mut drag_offset = (0.0, 0.0);
+    let mut show_labels = false;
+    let mut show_segment_lengths = false;
+    let mut show_glow = false;
+    let mut render_mode = RenderMode::Lines;
+    let mut show_tangents = false;
+    // Arrows from each point of the current step to where the next step puts it
+    let mut show_motion = false;
+    // The curve the animation converges to, drawn faintly underneath it
+    let mut show_limit = false;
+    // Index into CURVE_PALETTE
+    let mut curve_color_index = 0;
+    // While the animation runs, clicks neither add nor move points
+    let mut lock_editing_while_animating = true;
+    // Set once a locked click has been explained, until the button goes up
+    let mut locked_click = false;
+    let mut show_hull = false;
+    let mut selection_style = SelectionStyle::FilledRed;
+    let mut marker_shape = MarkerShape::Circle;
+    // Control points are drawn under the curve unless this is set
+    let mut points_on_top = false;
+    // Draw the curve as dashes crawling along it, showing which way it runs
+    let mut marching_ants = false;
+    let mut dash_offset = 0.0;
+    // How strongly the overlays are drawn, in percent
+    let mut overlay_opacity: u32 = 100;
+    // Points growing in after being added, or shrinking away after removal
+    let mut effects = PointEffects::new();
+    // Until when the centroid crosshair stays up after a rotate or scale
+    let mut show_centroid_until: Option<Instant> = None;
+    // Anti-aliasing for the curve and for the control points, each on its
+    // own. The points start out drawn at their sub-pixel positions so they
+    // glide while dragged instead of jumping a pixel at a time.
+    let mut aa_curve = false;
+    let mut aa_points = true;
+    // Redistribute the drawn curve's points evenly along its length
+    let mut even_spacing = false;
+    // Draw the scene this many times larger in each direction and average it
+    // down, which smooths every edge at the cost of memory and time
+    let mut supersample: usize = 1;
+    let mut scene_buffer: Vec<u32> = Vec::new();
+    // The glow's coverage mask, kept between frames to reuse its memory
+    let mut glow_mask: Vec<f32> = Vec::new();
+    let mut compare_ratios = false;
+    let mut snap_to_grid = false;
+    // Dragging sketches a stroke of points instead of moving the selection;
+    // `stroke_start` is the index of the stroke's first point while drawing
+    let mut freehand = false;
+    let mut stroke_start: Option<usize> = None;
+    // Centre and radius of the ring being dragged out, in curve coordinates
+    let mut ring_drag: Option<(Point2<f32>, f32)> = None;
+    let mut weights = WeightSource::new(args.pressure_from_speed);
+    // Editing sessions, being recorded and being played back
+    let mut recorder: Option<Recorder> = None;
+    let mut player: Option<Player> = None;
+    // Set for the frame whose finished picture is saved to a PNG
+    let mut take_screenshot = false;
+    // Mark where snapped points were actually clicked
+    let mut show_raw_clicks = false;
+    // Arrow keys nudge the selection, with their own repeat and acceleration
+    let mut nudge_keys = [
+        (Key::Left, -1.0, 0.0, KeyRepeater::new()),
+        (Key::Right, 1.0, 0.0, KeyRepeater::new()),
+        (Key::Up, 0.0, -1.0, KeyRepeater::new()),
+        (Key::Down, 0.0, 1.0, KeyRepeater::new()),
+    ];
+    // Overlay every earlier subdivision level, fading with age; `step_stride`
+    // thins it out to every n-th level counted back from the current one
+    let mut show_all_steps = false;
+    let mut step_stride: usize = 1;
+    // Clamp keeps reporting the window border while the cursor is outside,
+    // which lets points be dragged flush against the edge. Pass reports the
+    // real position even past the border (negative or beyond the window
+    // size), so a drag can carry points off screen
+    let mut mouse_mode = MouseMode::Discard;
+    // Keep added and dragged points inside the visible area
+    let mut clamp_to_window = false;
+    let mut show_help = false;
+    // The view being drawn, and the one zooming, fitting and resetting ask
+    // for. With smooth view changes on, the first eases toward the second
+    // over a few frames; panning by hand moves both, so it never lags.
+    let mut viewport = Viewport::new();
+    let mut view_target = Viewport::new();
+    let mut smooth_view = true;
+    let mut last_view_update = Instant::now();
+    // Last cursor position while panning with the right button
+    let mut pan_anchor: Option<(f32, f32)> = None;
+    let mut show_point_list = false;
+    let mut point_list = PointList::new();
+    let mut show_minimap = false;
+    // Open while the user is typing the coordinates of a new point
+    let mut coord_entry: Option<CoordEntry> = None;
+    let mut gallery = Gallery::new();
+    // Frames are only drawn while something could have changed; the first
+    // one always is
+    let mut was_active = true;
+    let mut last_mouse_pos: Option<(f32, f32)> = None;
+    // The step shown in the HUD, with its distance from the limit curve
+    let mut step_error: Option<(Vec<Point2<f32>>, f32)> = None;
 
-        // For open curves, keep the first and last points
-        result.push(points[0]);
+    'running: while window.is_open() {
+        let mouse_pos = window.get_mouse_pos(mouse_mode);
+        let mouse_moved = mouse_pos != last_mouse_pos;
+        last_mouse_pos = mouse_pos;
 
-        for i in 0..points.len() - 1 {
-            let p0 = points[i];
-            let p1 = points[i + 1];
+        // The point list takes the left button while the cursor is over it, or
+        // until a row picked up from it is dropped
+        let point_count = chaikin.control_points.len();
+        let list_owns_mouse = show_point_list
+            && !dragging
+            && (point_list.is_dragging()
+                || window
+                    .get_mouse_pos(mouse_mode)
+                    .is_some_and(|(x, y)| point_list.is_over(x, y, point_count)));
+        let minimap = show_minimap.then(|| Minimap::new(&chaikin.control_points)).flatten();
+        let minimap_owns_mouse = minimap.is_some()
+            && !dragging
+            && !list_owns_mouse
+            && mouse_pos.is_some_and(|(x, y)| Minimap::is_over(x, y));
 
-            // Calculate 1/4 and 3/4 points (Chaikin's algorithm)
-            // Instead of adding points directly, we interpolate between them
-            let q = Point2::new(
-                p0.x * 0.75 + p1.x * 0.25,
-                p0.y * 0.75 + p1.y * 0.25
-            );
-            
-            let r = Point2::new(
-                p0.x * 0.25 + p1.x * 0.75,
-                p0.y * 0.25 + p1.y * 0.75
+        // Everything the mouse and keyboard ask for this frame, applied in order
+        // once all input has been read
+        let mut actions: Vec<Action> = Vec::new();
+
+        // The visible area, in curve coordinates, follows panning and zooming
+        chaikin.keep_within = clamp_to_window.then(|| {
+            let corner = Point2::new(WIDTH as f32, HEIGHT as f32);
+            (viewport.screen_to_world(Point2::origin()), viewport.screen_to_world(corner))
+        });
+
+        // Handle mouse input
+        if list_owns_mouse {
+            let moved = point_list.update(
+                window.get_mouse_pos(mouse_mode),
+                window.get_mouse_down(MouseButton::Left),
+                point_count,
             );
+            if let Some((from, to)) = moved {
+                actions.push(Action::ReorderPoint { from, to });
+            }
+        } else if minimap_owns_mouse {
+            // Clicking (or dragging) in the minimap centres the view there
+            if window.get_mouse_down(MouseButton::Left)
+                && let (Some(map), Some((x, y))) = (&minimap, mouse_pos)
+            {
+                let center = Point2::new(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+                view_target.center_on(map.world_at(x, y), center);
+            }
+        } else if window.get_mouse_down(MouseButton::Left)
+            && lock_editing_while_animating
+            && chaikin.animating
+            && !dragging
+        {
+            if !locked_click {
+                status.show("Points are locked while animating".to_string());
+                locked_click = true;
+            }
+        } else if window.get_mouse_down(MouseButton::Left) {
+            // With MouseMode::Discard there is no position while the cursor is
+            // outside the window; the dragged points simply wait where they were
+            if let Some((mouse_x, mouse_y)) = world_mouse_pos(&window, mouse_mode, &viewport) {
+                let add_point = |x, y, weight| {
+                    if snap_to_grid {
+                        Action::AddSnappedPoint { x, y, grid_size: GRID_SIZE, weight }
+                    } else {
+                        Action::AddPoint { x, y, weight }
+                    }
+                };
+                let cursor = Point2::new(mouse_x, mouse_y);
+                if let Some((center, radius)) = ring_drag.as_mut() {
+                    *radius = (cursor - *center).norm();
+                } else if !dragging && chaikin.control_points.is_empty() && alt_held(&window) {
+                    // Alt+drag on an empty canvas sizes a ring to start from
+                    ring_drag = Some((cursor, 0.0));
+                    dragging = true;
+                } else if freehand {
+                    // Sketching: a point where the stroke starts, then another
+                    // whenever the cursor is far enough from the last one
+                    let spacing = MIN_POINT_SPACING / viewport.zoom;
+                    let far_enough = chaikin
+                        .control_points
+                        .last()
+                        .is_none_or(|cp| (cp.position - cursor).norm() >= spacing);
+                    let weight = |weights: &mut WeightSource| {
+                        weights.weight(viewport.world_to_screen(cursor))
+                    };
+                    if !dragging {
+                        actions.push(Action::DeselectAll);
+                        stroke_start = Some(chaikin.control_points.len());
+                        dragging = true;
+                        actions.push(add_point(mouse_x, mouse_y, weight(&mut weights)));
+                    } else if far_enough {
+                        actions.push(add_point(mouse_x, mouse_y, weight(&mut weights)));
+                    }
+                } else if !dragging {
+                    if ctrl_held(&window) {
+                        // Ctrl+click adds or removes a point from the selection
+                        actions.push(Action::ToggleSelectionAt { x: mouse_x, y: mouse_y });
+                    } else if !chaikin.is_point_selected_at(mouse_x, mouse_y) {
+                        // Select the point being clicked on, if there is one
+                        actions.push(Action::SelectOnlyAt { x: mouse_x, y: mouse_y });
+                        if chaikin.point_index_at(mouse_x, mouse_y).is_none() {
+                            // If not, add a new point
+                            actions.push(add_point(mouse_x, mouse_y, 1.0));
+                        }
+                    }
+                    // Grabbing a point: remember the curve as it was to compare against
+                    if chaikin.point_index_at(mouse_x, mouse_y).is_some()
+                        && chaikin.control_points.len() >= 2
+                    {
+                        drag_ghost = Some(chaikin.final_curve());
+                    }
+                    dragging = true;
+                    drag_origin = (mouse_x, mouse_y);
+                    drag_offset = (0.0, 0.0);
+                } else {
+                    // Move the selected points along with the cursor
+                    let mut offset = (mouse_x - drag_origin.0, mouse_y - drag_origin.1);
+                    // Holding Shift locks the drag to whichever axis it has moved along most
+                    if shift_held(&window) {
+                        if offset.0.abs() >= offset.1.abs() {
+                            offset.1 = 0.0;
+                        } else {
+                            offset.0 = 0.0;
+                        }
+                    }
+                    actions.push(Action::MoveSelection {
+                        dx: offset.0 - drag_offset.0,
+                        dy: offset.1 - drag_offset.1,
+                    });
+                    drag_offset = offset;
+                }
+            }
+        } else {
+            // Releasing the button ends the drag even if it happens outside the
+            // window; the selection stays so it can be deleted
+            dragging = false;
+            locked_click = false;
+            drag_ghost = None;
+            if let Some(start) = stroke_start.take() {
+                weights.end_stroke();
+                actions.push(Action::FinishStroke { start });
+            }
+            if let Some((center, radius)) = ring_drag.take()
+                && radius * viewport.zoom >= MIN_RING_RADIUS
+            {
+                actions.push(Action::AddRing { x: center.x, y: center.y, radius });
+            }
+        }
+
+        // Right-drag pans the view, the scroll wheel zooms around the cursor
+        if window.get_mouse_down(MouseButton::Right) {
+            if let Some((x, y)) = window.get_mouse_pos(mouse_mode) {
+                if let Some((last_x, last_y)) = pan_anchor {
+                    viewport.pan(x - last_x, y - last_y);
+                    view_target.pan(x - last_x, y - last_y);
+                }
+                pan_anchor = Some((x, y));
+            }
+        } else {
+            pan_anchor = None;
+        }
 
-            result.push(q);
-            result.push(r);
+        let scroll = window.get_scroll_wheel().map_or(0.0, |(_, scroll)| scroll);
+        if scroll != 0.0
+            && let Some((x, y)) = window.get_mouse_pos(MouseMode::Clamp)
+        {
+            let factor = if scroll > 0.0 { ZOOM_STEP } else { 1.0 / ZOOM_STEP };
+            view_target.zoom_at(Point2::new(x, y), factor);
         }
 
-        result.push(*points.last().unwrap());
-        result
-    }
+        // Handle keyboard input. While coordinates are being typed every key
+        // goes to the entry box instead.
+        let shift = shift_held(&window);
+        let mut typed = false;
+        if let Some(entry) = coord_entry.as_mut() {
+            let mut close = false;
+            for key in window.get_keys_pressed(KeyRepeat::Yes) {
+                typed = true;
+                match entry.key(key) {
+                    Some(EntryEvent::Submit(p)) => {
+                        actions.push(Action::AddPoint { x: p.x, y: p.y, weight: 1.0 });
+                        close = true;
+                    }
+                    Some(EntryEvent::Invalid(message)) => status.show(message),
+                    Some(EntryEvent::Cancel) => close = true,
+                    None => {}
+                }
+            }
+            if close {
+                coord_entry = None;
+            }
+        } else {
+            for (key, dx, dy, repeater) in nudge_keys.iter_mut() {
+                if let Some(distance) = repeater.update(window.is_key_down(*key)) {
+                    actions.push(Action::MoveSelection {
+                        dx: *dx * distance,
+                        dy: *dy * distance,
+                    });
+                }
+            }
+
+            // Every bound key pressed this frame
+            actions.extend(
+                bindings
+                    .iter()
+                    .filter(|&(&key, action)| {
+                        let repeat = if action.repeats() { KeyRepeat::Yes } else { KeyRepeat::No };
+                        window.is_key_pressed(key, repeat)
+                    })
+                    .map(|(_, &action)| if shift { action.shifted() } else { action }),
+            );
+        }
+
+        // Keys aimed at the point or segment under the cursor are pinned to
+        // where it is now, and dropped while it is outside the window
+        let cursor = world_mouse_pos(&window, mouse_mode, &viewport);
+        actions = actions
+            .into_iter()
+            .filter_map(|action| match cursor {
+                Some((x, y)) => Some(action.at_cursor(x, y)),
+                None => (!action.needs_cursor()).then_some(action),
+            })
+            .collect();
 
-    fn update_animation(&mut self) {
-        if !self.animating || self.animation_steps.is_empty() {
-            return;
+        // Only what is done live is recorded, not what a playback replays
+        if let Some(recording) = &mut recorder {
+            for &action in &actions {
+                if action.is_recordable() {
+                    recording.record(action);
+                }
+            }
+        }
+        // A session being played back acts alongside whatever is done live
+        if let Some(playing) = &mut player {
+            actions.extend(playing.due());
+            if playing.is_finished() {
+                player = None;
+                status.show("Playback finished");
+            }
         }
 
-        if self.last_step_time.elapsed() >= ANIMATION_STEP_DURATION {
-            self.current_step = (self.current_step + 1) % self.animation_steps.len();
-            self.last_step_time = Instant::now();
+        if actions.iter().any(|action| action.is_transform()) {
+            show_centroid_until = Some(Instant::now() + CENTROID_MARKER_LINGER);
         }
-    }
+        let show_centroid = show_centroid_until.is_some_and(|until| Instant::now() < until);
+
+        let input_active = !actions.is_empty()
+            || typed
+            || mouse_moved
+            || scroll != 0.0
+            || window.get_mouse_down(MouseButton::Left)
+            || window.get_mouse_down(MouseButton::Right);
 
-    fn select_point_at(&mut self, x: f32, y: f32) -> bool {
-        for point in &mut self.control_points {
-            let dx = point.position.x - x;
-            let dy = point.position.y - y;
-            if dx * dx + dy * dy <= (POINT_RADIUS as f32 * POINT_RADIUS as f32) {
-                point.selected = true;
-                return true;
+        for action in actions {
+            match action {
+                Action::ToggleRecording => match recorder.take() {
+                    Some(recording) => match recording.save(SESSION_FILE) {
+                        Ok(()) => status.show(format!(
+                            "Saved {} recorded actions to {}",
+                            recording.len(),
+                            SESSION_FILE
+                        )),
+                        Err(err) => eprintln!("Could not save {}: {}", SESSION_FILE, err),
+                    },
+                    None => {
+                        recorder = Some(Recorder::start(&chaikin));
+                        status.show("Recording; press again to stop and save");
+                    }
+                },
+                Action::PlaySession => {
+                    if player.take().is_some() {
+                        status.show("Playback stopped");
+                    } else {
+                        match Player::load(SESSION_FILE) {
+                            Ok(playing) => {
+                                playing.reset(&mut chaikin);
+                                player = Some(playing);
+                                status.show(format!("Playing back {}", SESSION_FILE));
+                            }
+                            Err(err) => eprintln!("Could not load {}: {}", SESSION_FILE, err),
+                        }
+                    }
+                }
+                Action::ToggleClampToWindow => {
+                    clamp_to_window = !clamp_to_window;
+                    status.show(if clamp_to_window {
+                        "Points are kept inside the window"
+                    } else {
+                        "Points can go anywhere"
+                    });
+                }
+                Action::CycleMouseMode => {
+                    mouse_mode = match mouse_mode {
+                        MouseMode::Discard => MouseMode::Clamp,
+                        MouseMode::Clamp => MouseMode::Pass,
+                        MouseMode::Pass => MouseMode::Discard,
+                    };
+                    status.show(format!("Mouse mode: {:?}", mouse_mode));
+                }
+                // Writes the control polygon, or with Shift the smoothed curve
+                Action::ExportObj => {
+                    let source = if shift {
+                        ExportSource::SmoothedCurve
+                    } else {
+                        ExportSource::ControlPolygon
+                    };
+                    match chaikin.export_obj(OBJ_FILE, source) {
+                        Ok(()) => println!("Exported {:?} to {}", source, OBJ_FILE),
+                        Err(err) => eprintln!("Could not export {}: {}", OBJ_FILE, err),
+                    }
+                }
+                Action::ImportObj => {
+                    match chaikin.import_obj(OBJ_FILE) {
+                        Ok(()) => println!(
+                            "Imported {} points from {}",
+                            chaikin.control_points.len(),
+                            OBJ_FILE
+                        ),
+                        Err(err) => eprintln!("Could not import {}: {}", OBJ_FILE, err),
+                    }
+                }
+                Action::ImportSvg => match chaikin.import_svg(SVG_FILE) {
+                    Ok(approximated) => {
+                        println!(
+                            "Imported {} points from {}",
+                            chaikin.control_points.len(),
+                            SVG_FILE
+                        );
+                        if !approximated.is_empty() {
+                            let commands: String = approximated.iter().collect();
+                            let message =
+                                format!("Curve commands {} imported as straight lines", commands);
+                            println!("{}", message);
+                            status.show(message);
+                        }
+                    }
+                    Err(err) => eprintln!("Could not import {}: {}", SVG_FILE, err),
+                },
+                Action::ToggleAllSteps => show_all_steps = !show_all_steps,
+                Action::FewerOverlaidSteps => step_stride = (step_stride - 1).max(1),
+                Action::MoreOverlaidSteps => {
+                    step_stride = (step_stride + 1).min(chaikin.max_steps.max(1));
+                }
+                Action::CycleRenderMode => render_mode = render_mode.next(),
+                // Lifting the frame cap is handy for profiling; the FPS readout shows
+                // while it is off
+                Action::ToggleFrameLimit => {
+                    rate_limited = !rate_limited;
+                    window.limit_update_rate(if rate_limited { Some(FRAME_TIME) } else { None });
+                }
+                Action::CycleMarkerShape => {
+                    marker_shape = marker_shape.next();
+                    status.show(format!("Marker shape: {:?}", marker_shape));
+                }
+                Action::CycleSelectionStyle => {
+                    selection_style = match selection_style {
+                        SelectionStyle::FilledRed => SelectionStyle::Ring,
+                        SelectionStyle::Ring => SelectionStyle::FilledRed,
+                    };
+                }
+                Action::ToggleAntialias => aa_curve = !aa_curve,
+                Action::ToggleAntialiasPoints => aa_points = !aa_points,
+                Action::TogglePointsOnTop => points_on_top = !points_on_top,
+                Action::ToggleMarchingAnts => marching_ants = !marching_ants,
+                Action::RaiseOverlayOpacity | Action::LowerOverlayOpacity => {
+                    overlay_opacity = if action == Action::RaiseOverlayOpacity {
+                        (overlay_opacity + OVERLAY_OPACITY_STEP).min(100)
+                    } else {
+                        overlay_opacity.saturating_sub(OVERLAY_OPACITY_STEP)
+                    };
+                    status.show(format!("Overlay opacity {}%", overlay_opacity));
+                }
+                Action::ToggleEvenSpacing => even_spacing = !even_spacing,
+                Action::CycleSupersample => {
+                    supersample = match supersample {
+                        1 => 2,
+                        2 => 4,
+                        _ => 1,
+                    };
+                    if supersample == 1 {
+                        // Give the memory back while it is off
+                        scene_buffer = Vec::new();
+                    }
+                    status.show(format!("Supersampling: {}x", supersample));
+                }
+                Action::ToggleCompareRatios => compare_ratios = !compare_ratios,
+                Action::ToggleHelp => show_help = !show_help,
+                Action::ToggleSnap => snap_to_grid = !snap_to_grid,
+                Action::ToggleFreehand => {
+                    freehand = !freehand;
+                    let mode = if freehand { "Freehand drawing" } else { "Click to place points" };
+                    status.show(mode);
+                }
+                Action::ToggleRawClicks => show_raw_clicks = !show_raw_clicks,
+                Action::ToggleTangents => show_tangents = !show_tangents,
+                Action::ToggleMotion => show_motion = !show_motion,
+                Action::ToggleLimitCurve => show_limit = !show_limit,
+                Action::ToggleEditLock => {
+                    lock_editing_while_animating = !lock_editing_while_animating;
+                    status.show(if lock_editing_while_animating {
+                        "Points locked while animating".to_string()
+                    } else {
+                        "Points editable while animating".to_string()
+                    });
+                }
+                Action::CycleCurveColor => {
+                    curve_color_index = (curve_color_index + 1) % CURVE_PALETTE.len();
+                    status.show(format!(
+                        "Curve color {} of {}",
+                        curve_color_index + 1,
+                        CURVE_PALETTE.len()
+                    ));
+                }
+                Action::ToggleHull => show_hull = !show_hull,
+                Action::ToggleGlow => show_glow = !show_glow,
+                // Print a command line that reopens the current points with the
+                // current settings
+                Action::PrintCommandLine => {
+                    let positions: Vec<Point2<f32>> =
+                        chaikin.control_points.iter().map(|cp| cp.position).collect();
+                    // Points unchanged since they were loaded can be loaded
+                    // again from the same file
+                    let input = match (&args.input, &input_points) {
+                        (Some(path), Some(loaded)) if *loaded == positions => Ok(path.clone()),
+                        _ => chaikin.save_points(SNAPSHOT_FILE).map(|()| SNAPSHOT_FILE.into()),
+                    };
+                    match input {
+                        Ok(input) => {
+                            let settings = Args {
+                                input: Some(input),
+                                iterations: Some(chaikin.max_steps),
+                                max_points: chaikin.max_control_points,
+                                closed: chaikin.closed,
+                                ratio: (chaikin.ratio != DEFAULT_RATIO).then_some(chaikin.ratio),
+                                save_on_exit: args.save_on_exit,
+                                pressure_from_speed: args.pressure_from_speed,
+                                trace: args.trace.clone(),
+                                normalize: chaikin.normalize_exports,
+                                jitter: (chaikin.jitter > 0.0).then_some(chaikin.jitter),
+                                jitter_seed: (chaikin.jitter > 0.0).then_some(chaikin.jitter_seed),
+                                ..Args::default()
+                            };
+                            println!("{}", settings.to_command_line());
+                            // The points file only holds positions
+                            let mut lost = Vec::new();
+                            if chaikin.segment_ratios.iter().any(Option::is_some) {
+                                lost.push("per-segment ratios");
+                            }
+                            if chaikin.control_points.iter().any(|cp| cp.cusp) {
+                                lost.push("cusps");
+                            }
+                            if chaikin.control_points.iter().any(|cp| cp.locked) {
+                                lost.push("locks");
+                            }
+                            if !lost.is_empty() {
+                                println!("Not carried over: {}", lost.join(", "));
+                            }
+                        }
+                        Err(err) => eprintln!("Could not save {}: {}", SNAPSHOT_FILE, err),
+                    }
+                }
+                Action::ExportSteps => {
+                    match chaikin.export_steps_json(STEPS_FILE) {
+                        Ok(()) => println!("Exported every subdivision level to {}", STEPS_FILE),
+                        Err(err) => eprintln!("Could not export {}: {}", STEPS_FILE, err),
+                    }
+                }
+                Action::ExportStepsCsv => {
+                    match chaikin.export_steps_csv(STEPS_CSV_FILE) {
+                        Ok(()) => println!(
+                            "Exported the point count and length of every level to {}",
+                            STEPS_CSV_FILE
+                        ),
+                        Err(err) => eprintln!("Could not export {}: {}", STEPS_CSV_FILE, err),
+                    }
+                }
+                Action::Screenshot => take_screenshot = true,
+                Action::ToggleLabels => show_labels = !show_labels,
+                Action::ToggleSegmentLengths => show_segment_lengths = !show_segment_lengths,
+                Action::TogglePointList => show_point_list = !show_point_list,
+                Action::ToggleMinimap => show_minimap = !show_minimap,
+                Action::NextCurveFile | Action::PreviousCurveFile => {
+                    let forward = action == Action::NextCurveFile;
+                    match gallery.step(&mut chaikin, forward) {
+                        Some(path) => {
+                            let name = path.file_name().unwrap_or_default().to_string_lossy();
+                            window.set_title(&format!("{} - {}", WINDOW_TITLE, name));
+                            status.show(format!("Loaded {}", name));
+                        }
+                        None => status.show("No saved curves in the current directory"),
+                    }
+                }
+                Action::EnterCoordinates => coord_entry = Some(CoordEntry::new()),
+                Action::ResetView => view_target = Viewport::new(),
+                Action::ToggleSmoothView => {
+                    smooth_view = !smooth_view;
+                    status.show(if smooth_view {
+                        "Zooming and fitting the view ease into place"
+                    } else {
+                        "Zooming and fitting the view are instant"
+                    });
+                }
+                Action::FitView => {
+                    let positions: Vec<Point2<f32>> =
+                        chaikin.control_points.iter().map(|cp| cp.position).collect();
+                    view_target.fit(&positions, WIDTH as f32, HEIGHT as f32, FIT_MARGIN);
+                }
+                Action::Quit => break 'running,
+                _ => {
+                    let positions = |chaikin: &ChaikinCurve| -> Vec<Point2<f32>> {
+                        chaikin.control_points.iter().map(|cp| cp.position).collect()
+                    };
+                    let before = positions(&chaikin);
+                    if let Some(message) = apply_action(&mut chaikin, action) {
+                        status.show(message);
+                    }
+                    effects.compare(&before, &positions(&chaikin));
+                }
             }
         }
-        false
-    }
 
-    fn deselect_all_points(&mut self) {
-        for point in &mut self.control_points {
-            point.selected = false;
+        // Points far from the origin are brought back to where f32 is
+        // precise, with the view moved to match. Not mid-drag, where the
+        // drag origin is in curve coordinates, nor while a session is being
+        // recorded or played, whose actions are.
+        if !dragging
+            && stroke_start.is_none()
+            && recorder.is_none()
+            && player.is_none()
+            && let Some(shift) = chaikin.recenter(GRID_SIZE)
+        {
+            viewport.offset += shift * viewport.zoom;
+            view_target.offset += shift * view_target.zoom;
         }
-    }
 
-    fn move_selected_point(&mut self, x: f32, y: f32) {
-        for point in &mut self.control_points {
-            if point.selected {
-                point.position.x = x;
-                point.position.y = y;
+        let now = Instant::now();
+        if smooth_view {
+            let elapsed = (now - last_view_update).as_secs_f32();
+            viewport.ease_toward(&view_target, 1.0 - (-elapsed / VIEW_EASE_TIME).exp());
+        } else {
+            viewport = view_target;
+        }
+        last_view_update = now;
+
+        // Update animation
+        chaikin.update_animation();
+
+        autosave.update(&chaikin);
+        effects.prune();
+
+        // Skip drawing while the picture can't have changed: no input, no
+        // animation step coming and no message about to disappear. The frame
+        // after the last activity is still drawn, so whatever just stopped
+        // shows in its final state. The FPS readout (frame limit off) needs
+        // every frame.
+        let active = input_active
+            || player.is_some()
+            || marching_ants
+            || show_centroid
+            || effects.is_active()
+            || chaikin.step_progress().is_some()
+            || status.is_showing()
+            || viewport != view_target
+            || !rate_limited;
+        let redraw = active || was_active;
+        was_active = active;
+        if !redraw {
+            window.update();
+            continue;
+        }
+        if marching_ants {
+            dash_offset = (dash_offset + MARCH_STEP) % (CURVE_DASH * 2.0);
+        }
+
+        // The scene goes into the supersampled buffer when there is one, at
+        // `scale` times the size; text and panels are drawn afterwards at the
+        // window's own resolution
+        let scale = supersample as i32;
+        let view = viewport.scaled(scale as f32);
+        if supersample > 1 {
+            scene_buffer.resize(WIDTH * HEIGHT * supersample * supersample, 0);
+        }
+        let (scene_width, scene_height) = (WIDTH as i32 * scale, HEIGHT as i32 * scale);
+        let scene_pixels: &mut [u32] =
+            if supersample > 1 { &mut scene_buffer } else { &mut buffer };
+        let scene = &mut Canvas::new(scene_pixels, scene_width as usize, scene_height as usize);
+
+        // Clear the buffer
+        for i in scene.pixels.iter_mut() {
+            *i = 0;
+        }
+        if let Some(background) = &background {
+            background.draw(scene, &view);
+        }
+
+        // Overlays fade towards black with the overlay opacity; the curve
+        // and the control points always stay at full strength
+        let opacity = overlay_opacity as f32 / 100.0;
+        let overlay = |color: u32| blend_color(0xFF000000, color, opacity);
+
+        let spacing = GRID_SIZE * view.zoom;
+        if snap_to_grid && spacing >= MIN_GRID_SPACING * scale as f32 {
+            // Start from the last grid line above and left of the window corner
+            let corner = view.screen_to_world(Point2::origin());
+            let first = view.world_to_screen(Point2::new(
+                (corner.x / GRID_SIZE).floor() * GRID_SIZE,
+                (corner.y / GRID_SIZE).floor() * GRID_SIZE,
+            ));
+            let mut x = first.x;
+            while x < scene_width as f32 {
+                let x_px = x.round() as i32;
+                draw_line(scene, x_px, 0, x_px, scene_height - 1, overlay(GRID_COLOR));
+                x += spacing;
+            }
+            let mut y = first.y;
+            while y < scene_height as f32 {
+                let y_px = y.round() as i32;
+                draw_line(scene, 0, y_px, scene_width - 1, y_px, overlay(GRID_COLOR));
+                y += spacing;
             }
         }
-    }
 
-    fn get_current_points(&self) -> Vec<Point2<f32>> {
-        if self.animating && !self.animation_steps.is_empty() {
-            // Return the current step of the animation
-            self.animation_steps[self.current_step].clone()
-        } else if self.control_points.len() >= 2 {
-            // Return just the control points when not animating
-            self.control_points.iter().map(|cp| cp.position).collect()
-        } else if self.control_points.len() == 1 {
-            // Return the single point
-            vec![self.control_points[0].position]
+        let curve_color = CURVE_PALETTE[curve_color_index];
+
+        // Everything below is drawn in screen space
+        let current_points = view.points_to_screen(&chaikin.get_current_points());
+        let closed = chaikin.is_closed() && chaikin.revealed_points.is_none();
+        let outline = close_loop(&current_points, closed);
+        // Only what is drawn is resampled; the steps themselves are untouched
+        let outline = if even_spacing && outline.len() >= 2 {
+            resample_by_arc_length(&outline, EVEN_SPACING * scale as f32)
         } else {
-            // No points at all
-            vec![]
+            outline
+        };
+
+        // The glow goes down first so the points and the crisp curve sit on top of it
+        if show_glow {
+            let radius = GLOW_RADIUS * scale as f32;
+            draw_glow(scene, &mut glow_mask, &outline, radius, curve_color, GLOW_ALPHA);
         }
-    }
-}
 
-fn draw_point(buffer: &mut Vec<u32>, x: i32, y: i32, color: u32) {
-    if x >= 0 && x < WIDTH as i32 && y >= 0 && y < HEIGHT as i32 {
-        buffer[y as usize * WIDTH + x as usize] = color;
-    }
-}
+        // The curve always stays inside the hull of its control points
+        if show_hull {
+            let hull = view.points_to_screen(&chaikin.convex_hull());
+            let dash = HULL_DASH * scale as f32;
+            draw_dashed_polyline(scene, &close_loop(&hull, true), dash, 0.0, overlay(HULL_COLOR));
+        }
+
+        if show_limit && chaikin.animating {
+            let limit = view.points_to_screen(&chaikin.limit_curve());
+            let color = overlay(blend_color(0xFF000000, curve_color, LIMIT_BRIGHTNESS));
+            draw_polyline(scene, &limit, color);
+        }
+
+        // Where the curve was before the point being dragged was picked up
+        if let Some(ghost) = &drag_ghost {
+            let ghost = view.points_to_screen(ghost);
+            let color = overlay(blend_color(0xFF000000, curve_color, GHOST_BRIGHTNESS));
+            draw_polyline(scene, &close_loop(&ghost, chaikin.is_closed()), color);
+        }
 
-fn draw_circle(buffer: &mut Vec<u32>, center_x: i32, center_y: i32, radius: i32, color: u32) {
-    for y in -radius..=radius {
-        for x in -radius..=radius {
-            if x * x + y * y <= radius * radius {
-                draw_point(buffer, center_x + x, center_y + y, color);
+        // Earlier subdivision levels, dimmest first so newer ones land on top
+        if show_all_steps && chaikin.animating {
+            let current = chaikin.current_step;
+            for (level, points) in chaikin.animation_steps[..current].iter().enumerate() {
+                if !(current - level).is_multiple_of(step_stride) {
+                    continue;
+                }
+                let age = level as f32 / current as f32;
+                let brightness = OLDEST_STEP_BRIGHTNESS
+                    + (NEWEST_STEP_BRIGHTNESS - OLDEST_STEP_BRIGHTNESS) * age;
+                let color = overlay(blend_color(0xFF000000, curve_color, brightness));
+                let points = view.points_to_screen(points);
+                draw_polyline(scene, &close_loop(&points, closed), color);
             }
         }
-    }
-}
 
-fn draw_line(buffer: &mut Vec<u32>, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
-    let dx = (x1 - x0).abs();
-    let dy = -(y1 - y0).abs();
-    
-    let sx = if x0 < x1 { 1 } else { -1 };
-    let sy = if y0 < y1 { 1 } else { -1 };
-    
-    let mut err = dx + dy;
-    let mut x = x0;
-    let mut y = y0;
-    
-    loop {
-        draw_point(buffer, x, y, color);
-        if x == x1 && y == y1 {
-            break;
-        }
-        
-        let e2 = 2 * err;
-        if e2 >= dy {
-            if x == x1 {
-                break;
+        // Faint marker where each snapped point was clicked, tied to where it landed
+        if show_raw_clicks {
+            for point in chaikin.visible_control_points() {
+                if let Some(raw) = point.raw_position {
+                    let raw = view.world_to_screen(raw);
+                    let snapped = view.world_to_screen(point.position);
+                    draw_segment(scene, raw, snapped, overlay(RAW_CLICK_COLOR));
+                    let (x, y) = (raw.x.round() as i32, raw.y.round() as i32);
+                    draw_circle(scene, x, y, 2 * scale, overlay(RAW_CLICK_COLOR));
+                }
             }
-            err += dy;
-            x += sx;
         }
-        if e2 <= dx {
-            if y == y1 {
-                break;
+
+        // Draw all control points, under the curve or on top of it
+        let draw_control_points = |scene: &mut Canvas| {
+            // Removed points shrink and fade out where they were
+            for (point, left) in effects.vanishing() {
+                let position = view.world_to_screen(point);
+                let size = (POINT_RADIUS * scale) as f32 * left;
+                let color = blend_color(0xFF000000, POINT_COLOR, left);
+                if aa_points {
+                    draw_marker_aa(scene, position, size, marker_shape, color);
+                } else {
+                    let (x, y) = (position.x.round() as i32, position.y.round() as i32);
+                    draw_marker(scene, x, y, size.round() as i32, marker_shape, color);
+                }
+            }
+            for point in chaikin.visible_control_points() {
+                let position = view.world_to_screen(point.position);
+                let x = position.x.round() as i32;
+                let y = position.y.round() as i32;
+                let filled_red = point.selected && selection_style == SelectionStyle::FilledRed;
+                let color = if filled_red { SELECTED_COLOR } else { POINT_COLOR };
+                let size = (POINT_RADIUS * scale) as f32 * effects.scale_at(point.position);
+                if aa_points {
+                    draw_marker_aa(scene, position, size, marker_shape, color);
+                } else {
+                    draw_marker(scene, x, y, size.round() as i32, marker_shape, color);
+                }
+                if point.selected && selection_style == SelectionStyle::Ring {
+                    let r = (POINT_RADIUS + SELECTION_RING_GAP) * scale;
+                    if aa_points {
+                        draw_ring_aa(scene, position, r as f32, SELECTED_COLOR);
+                    } else {
+                        draw_ring(scene, x, y, r, SELECTED_COLOR);
+                    }
+                }
+                if point.locked {
+                    let r = (POINT_RADIUS + LOCK_MARKER_GAP) * scale;
+                    draw_line(scene, x - r, y - r, x + r, y - r, LOCK_COLOR);
+                    draw_line(scene, x + r, y - r, x + r, y + r, LOCK_COLOR);
+                    draw_line(scene, x + r, y + r, x - r, y + r, LOCK_COLOR);
+                    draw_line(scene, x - r, y + r, x - r, y - r, LOCK_COLOR);
+                }
+                if point.cusp {
+                    let r = (POINT_RADIUS + CUSP_MARKER_GAP) * scale;
+                    draw_line(scene, x, y - r, x + r, y, CUSP_COLOR);
+                    draw_line(scene, x + r, y, x, y + r, CUSP_COLOR);
+                    draw_line(scene, x, y + r, x - r, y, CUSP_COLOR);
+                    draw_line(scene, x - r, y, x, y - r, CUSP_COLOR);
+                }
             }
-            err += dx;
-            y += sy;
+        };
+        if !points_on_top {
+            draw_control_points(scene);
         }
-    }
-}
 
-fn main() {
-    let mut buffer: Vec<u32> = vec![0; WIDTH * HEIGHT];
-    
-    let mut window = Window::new(
-        "Chaikin Curve Animation",
-        WIDTH,
-        HEIGHT,
-        WindowOptions {
-            scale: Scale::X1,
-            ..WindowOptions::default()
-        },
-    )
-    .unwrap();
+        // Draw the current curve
+        if current_points.len() == 1 {
+            // Draw just the point
+            draw_circle(
+                scene,
+                current_points[0].x.round() as i32,
+                current_points[0].y.round() as i32,
+                3 * scale,
+                curve_color,
+            );
+        } else if render_mode == RenderMode::Points {
+            let radius = CURVE_DOT_RADIUS * scale;
+            for point in &outline {
+                if aa_curve {
+                    draw_marker_aa(scene, *point, radius as f32, MarkerShape::Circle, curve_color);
+                } else {
+                    let (x, y) = (point.x.round() as i32, point.y.round() as i32);
+                    draw_circle(scene, x, y, radius, curve_color);
+                }
+            }
+        } else if render_mode == RenderMode::Curvature {
+            for i in 1..outline.len() {
+                let curvature = (curvature_at(&outline, i - 1)
+                    + curvature_at(&outline, i))
+                    / 2.0;
+                // Square root spreads the ramp out over the gentler bends
+                let t = (curvature / MAX_DISPLAY_CURVATURE).sqrt().min(1.0);
+                let color = blend_color(STRAIGHT_COLOR, SHARP_COLOR, t);
+                draw_segment(scene, outline[i - 1], outline[i], color);
+            }
+        } else if render_mode == RenderMode::Rainbow {
+            let length: f32 = outline.windows(2).map(|s| (s[1] - s[0]).norm()).sum();
+            let mut along = 0.0;
+            for segment in outline.windows(2) {
+                // Coloured by the middle of the segment
+                let middle = along + (segment[1] - segment[0]).norm() / 2.0;
+                let hue = RAINBOW_HUES * middle / length.max(f32::EPSILON);
+                draw_segment(scene, segment[0], segment[1], hue_color(hue));
+                along += (segment[1] - segment[0]).norm();
+            }
+        } else if marching_ants {
+            let dash = CURVE_DASH * scale as f32;
+            draw_dashed_polyline(scene, &outline, dash, dash_offset * scale as f32, curve_color);
+        } else if aa_curve {
+            draw_polyline_aa(scene, &outline, curve_color);
+        } else {
+            // Draw line segments
+            draw_polyline(scene, &outline, curve_color);
+        }
 
-    // We don't want to tear now
-    window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
+        if points_on_top {
+            draw_control_points(scene);
+        }
 
-    let mut chaikin = ChaikinCurve::new();
-    let mut dragging = false;
+        // Where the points of the ring being dragged out will go
+        if let Some((center, radius)) = ring_drag {
+            let r = POINT_RADIUS * scale;
+            for point in ring_points(center, radius, RING_POINTS) {
+                let point = view.world_to_screen(point);
+                draw_ring(scene, point.x.round() as i32, point.y.round() as i32, r, POINT_COLOR);
+            }
+        }
 
-    while window.is_open() && !window.is_key_down(Key::Escape) {
-        // Clear the buffer
-        for i in buffer.iter_mut() {
-            *i = 0;
+        // The same polygon smoothed at a few different ratios; the legend is
+        // text, so it goes on with the overlays
+        if compare_ratios && chaikin.control_points.len() >= 2 {
+            for (ratio, color) in COMPARED_RATIOS {
+                let curve = view.points_to_screen(&chaikin.final_curve_with_ratio(ratio));
+                let curve = close_loop(&curve, chaikin.is_closed());
+                draw_polyline(scene, &curve, color);
+            }
         }
 
-        // Handle mouse input
-        if let Some((x, y)) = window.get_mouse_pos(MouseMode::Discard) {
-            let mouse_x = x as f32;
-            let mouse_y = y as f32;
-
-            if window.get_mouse_down(MouseButton::Left) {
-                if !dragging {
-                    // Check if we're clicking on an existing point
-                    if !chaikin.select_point_at(mouse_x, mouse_y) {
-                        // If not, add a new point
-                        chaikin.add_point(mouse_x, mouse_y);
-                        chaikin.reset_animation();
-                    }
-                    dragging = true;
-                } else {
-                    // Move the selected point
-                    chaikin.move_selected_point(mouse_x, mouse_y);
-                    if chaikin.animating {
-                        chaikin.generate_animation_steps();
+        // What rotating and scaling turn around
+        if show_centroid && let Some(centroid) = chaikin.centroid() {
+            let centroid = view.world_to_screen(centroid);
+            let (x, y) = (centroid.x.round() as i32, centroid.y.round() as i32);
+            let r = CENTROID_MARKER_SIZE * scale;
+            draw_line(scene, x - r, y, x + r, y, overlay(CENTROID_COLOR));
+            draw_line(scene, x, y - r, x, y + r, overlay(CENTROID_COLOR));
+        }
+
+        // Tangent direction at each control point, estimated from its neighbours
+        if show_tangents {
+            let positions: Vec<Point2<f32>> =
+                chaikin.control_points.iter().map(|cp| cp.position).collect();
+            for (i, p) in positions.iter().enumerate() {
+                if let Some(tangent) = tangent_at(&positions, i) {
+                    let p = view.world_to_screen(*p);
+                    let a = p - tangent * TANGENT_LENGTH * scale as f32;
+                    let b = p + tangent * TANGENT_LENGTH * scale as f32;
+                    draw_segment(scene, a, b, overlay(TANGENT_COLOR));
+                }
+            }
+        }
+
+        // Point counts differ between levels, so each point is paired with
+        // the two nearest points of the next level: the cuts either side of it
+        if show_motion
+            && chaikin.animating
+            && let Some(next) = chaikin.animation_steps.get(chaikin.current_step + 1)
+        {
+            let next = view.points_to_screen(next);
+            let head = MOTION_ARROW_HEAD * scale as f32;
+            for &p in &current_points {
+                let distance = |q: &Point2<f32>| (q - p).norm_squared();
+                let mut nearest: [Option<Point2<f32>>; 2] = [None, None];
+                for &q in &next {
+                    if nearest[0].is_none_or(|n| distance(&q) < distance(&n)) {
+                        nearest = [Some(q), nearest[0]];
+                    } else if nearest[1].is_none_or(|n| distance(&q) < distance(&n)) {
+                        nearest[1] = Some(q);
                     }
                 }
-            } else {
-                if dragging {
-                    chaikin.deselect_all_points();
-                    dragging = false;
+                for q in nearest.into_iter().flatten() {
+                    draw_arrow(scene, p, q, head, overlay(MOTION_COLOR));
                 }
             }
         }
 
-        // Handle keyboard input
-        if window.is_key_pressed(Key::Enter, minifb::KeyRepeat::No) {
-            chaikin.start_animation();
+        // Text and panels go straight into the window's own buffer
+        let frame = &mut Canvas::new(&mut buffer, WIDTH, HEIGHT);
+        if supersample > 1 {
+            downsample(&scene_buffer, frame, supersample);
         }
 
-        if window.is_key_pressed(Key::C, minifb::KeyRepeat::No) {
-            chaikin.clear_points();
+        if compare_ratios && chaikin.control_points.len() >= 2 {
+            // Legend for the compared curves
+            for (i, &(ratio, color)) in COMPARED_RATIOS.iter().enumerate() {
+                let y = 8 + i as i32 * (GLYPH_HEIGHT + 4);
+                draw_line(frame, 8, y + GLYPH_HEIGHT / 2, 20, y + GLYPH_HEIGHT / 2, color);
+                draw_text(frame, 26, y, &format!("ratio {:.2}", ratio), color);
+            }
         }
 
-        // Update animation
-        chaikin.update_animation();
+        // Draw the index of each control point
+        if show_labels {
+            let positions: Vec<Point2<f32>> = chaikin
+                .visible_control_points()
+                .iter()
+                .map(|cp| viewport.world_to_screen(cp.position))
+                .collect();
+            draw_labels(frame, &layout_labels(&positions));
+        }
 
-        // Draw all control points
-        for point in &chaikin.control_points {
-            draw_circle(
-                &mut buffer, 
-                point.position.x as i32, 
-                point.position.y as i32, 
-                POINT_RADIUS, 
-                if point.selected { 0xFFFF0000 } else { 0xFFFFFFFF }
-            );
+        // Length of each control polygon segment, in curve coordinates,
+        // written across its midpoint
+        if show_segment_lengths {
+            let points = chaikin.visible_control_points();
+            let count = if chaikin.is_closed() && points.len() > 2 {
+                points.len()
+            } else {
+                points.len().saturating_sub(1)
+            };
+            for i in 0..count {
+                let (a, b) = (points[i].position, points[(i + 1) % points.len()].position);
+                let text = format!("{:.0}", (b - a).norm());
+                let mid = viewport.world_to_screen(nalgebra::center(&a, &b));
+                let x = mid.x.round() as i32 - text_width(&text) / 2;
+                let y = mid.y.round() as i32 - GLYPH_HEIGHT / 2;
+                draw_text(frame, x, y, &text, overlay(SEGMENT_LENGTH_COLOR));
+            }
         }
 
-        // Draw the current curve
-        let current_points = chaikin.get_current_points();
-        if current_points.len() == 1 {
-            // Draw just the point
-            draw_circle(
-                &mut buffer,
-                current_points[0].x as i32,
-                current_points[0].y as i32,
-                3,
-                0xFF00FF00,
+        if show_point_list {
+            point_list.draw(frame, &chaikin.control_points);
+        }
+
+        if show_minimap && let Some(map) = Minimap::new(&chaikin.control_points) {
+            map.draw(frame, &chaikin.control_points, &viewport);
+        }
+
+        // A loaded or panned curve can sit wholly outside the window, which
+        // looks like the points went missing, and smoothing a straight line
+        // changes nothing, which looks like a bug
+        let positions: Vec<Point2<f32>> =
+            chaikin.control_points.iter().map(|cp| cp.position).collect();
+        let off_screen = viewport
+            .points_to_screen(&positions)
+            .iter()
+            .filter(|p| p.x < 0.0 || p.y < 0.0 || p.x > WIDTH as f32 || p.y > HEIGHT as f32)
+            .count();
+        if off_screen > 0 {
+            let (noun, them) = if off_screen == 1 { ("point", "it") } else { ("points", "them") };
+            let text = format!(
+                "{} {} off-screen - press Home to bring {} into view",
+                off_screen, noun, them
             );
-        } else if current_points.len() >= 2 {
-            // Draw line segments
-            for i in 0..current_points.len() - 1 {
-                draw_line(
-                    &mut buffer,
-                    current_points[i].x as i32,
-                    current_points[i].y as i32,
-                    current_points[i + 1].x as i32,
-                    current_points[i + 1].y as i32,
-                    0xFF00FF00,
-                );
+            draw_notice(frame, &text);
+        } else if positions.len() >= 3 && are_collinear(&positions, COLLINEAR_EPSILON) {
+            draw_notice(frame, "Points are collinear - the curve is a straight line");
+        }
+
+        if let Some(progress) = chaikin.step_progress() {
+            draw_step_progress(frame, progress);
+        }
+        if chaikin.animating && chaikin.revealed_points.is_none() {
+            let step = chaikin.current_step;
+            // Without it a stopped animation looks the same as a stuck one
+            let note = chaikin.is_finished().then_some(if chaikin.direction > 0 {
+                "fully smoothed"
+            } else {
+                "back at the polygon"
+            });
+            // Measuring against the limit curve takes a while on big
+            // curves, so it is only redone when the step's points change
+            let level = chaikin.animation_steps.get(step).cloned().unwrap_or_default();
+            let error = match &step_error {
+                Some((measured, error)) if *measured == level => *error,
+                _ => {
+                    let error = chaikin.step_error(step);
+                    step_error = Some((level, error));
+                    error
+                }
+            };
+            draw_step_error(frame, step, error, note);
+        }
+
+        status.draw(frame);
+
+        // Follows the current mouse mode, so Pass shows where the cursor
+        // really is while it is outside the window
+        if let Some((x, y)) = window.get_mouse_pos(mouse_mode) {
+            let screen = Point2::new(x, y);
+            draw_cursor_position(frame, screen, viewport.screen_to_world(screen));
+        }
+
+        fps.tick();
+        if !rate_limited {
+            fps.draw(frame);
+        }
+        if chaikin.dedup_epsilon > 0.0 {
+            draw_merge_distance(frame, chaikin.dedup_epsilon);
+        }
+
+        if let Some(entry) = &coord_entry {
+            entry.draw(frame);
+        }
+
+        // On top of everything; the animation keeps running underneath
+        if show_help {
+            draw_help(frame, &bindings);
+        }
+
+        // Exactly what is on screen, overlays and all
+        if take_screenshot {
+            take_screenshot = false;
+            let path = screenshot_path();
+            match screenshot(&buffer, WIDTH, HEIGHT, &path) {
+                Ok(()) => status.show(format!("Saved the screen to {}", path.display())),
+                Err(err) => eprintln!("Could not save {}: {}", path.display(), err),
             }
         }
 
         // Update the window
-        window.update_with_buffer(&buffer, WIDTH, HEIGHT).unwrap();
+        if let Err(err) = window.update_with_buffer(&buffer, WIDTH, HEIGHT) {
+            eprintln!("Could not update the window: {}", err);
+            std::process::exit(1);
+        }
+    }
+
+    // Reached by Escape and by closing the window alike
+    if args.save_on_exit {
+        autosave.save_now(&chaikin);
     }
 }
\ No newline at end of file
@@ -0,0 +1,115 @@
+use chaikin::ControlPoint;
+use nalgebra::{Point2, Vector2};
+
+use crate::draw::{Canvas, blend_point, draw_line, draw_point};
+use crate::viewport::Viewport;
+use crate::{HEIGHT, WIDTH};
+
+const MAP_WIDTH: i32 = 160;
+const MAP_HEIGHT: i32 = 120;
+const MAP_MARGIN: i32 = 6;
+// Leaves room for the cursor readout along the bottom edge
+const MAP_BOTTOM_GAP: i32 = 22;
+// Space kept between the outermost points and the edge of the map
+const MAP_PADDING: f32 = 8.0;
+const PANEL_COLOR: u32 = 0xFF101018;
+const PANEL_ALPHA: f32 = 0.85;
+const BORDER_COLOR: u32 = 0xFF505060;
+const POLYGON_COLOR: u32 = 0xFF606060;
+const POINT_COLOR: u32 = 0xFFFFFFFF;
+const VIEW_COLOR: u32 = 0xFFFFD060;
+
+// Overview in the bottom right corner fitting every control point, with the
+// part of the drawing currently on screen outlined. Built each frame, as the
+// fit changes whenever the points do.
+pub struct Minimap {
+    // From curve coordinates to window pixels inside the map
+    view: Viewport,
+}
+
+impl Minimap {
+    // `None` while there is nothing to show
+    pub fn new(points: &[ControlPoint]) -> Option<Self> {
+        let first = points.first()?.position;
+        let (min, max) = points.iter().fold((first, first), |(min, max), cp| {
+            (min.inf(&cp.position), max.sup(&cp.position))
+        });
+
+        // A single point (or a straight line) still gets a sensible scale
+        let extent = (max - min).map(|v| v.max(1.0));
+        let zoom = ((MAP_WIDTH as f32 - MAP_PADDING * 2.0) / extent.x)
+            .min((MAP_HEIGHT as f32 - MAP_PADDING * 2.0) / extent.y);
+        let (left, top) = Self::corner();
+        let center = Vector2::new(
+            left as f32 + MAP_WIDTH as f32 / 2.0,
+            top as f32 + MAP_HEIGHT as f32 / 2.0,
+        );
+        let offset = center - min.coords.lerp(&max.coords, 0.5) * zoom;
+        Some(Self {
+            view: Viewport { offset, zoom },
+        })
+    }
+
+    fn corner() -> (i32, i32) {
+        (
+            WIDTH as i32 - MAP_MARGIN - MAP_WIDTH,
+            HEIGHT as i32 - MAP_BOTTOM_GAP - MAP_HEIGHT,
+        )
+    }
+
+    pub fn is_over(x: f32, y: f32) -> bool {
+        let (left, top) = Self::corner();
+        let (x, y) = (x as i32, y as i32);
+        x >= left && x < left + MAP_WIDTH && y >= top && y < top + MAP_HEIGHT
+    }
+
+    // Curve coordinates under a window position inside the map
+    pub fn world_at(&self, x: f32, y: f32) -> Point2<f32> {
+        self.view.screen_to_world(Point2::new(x, y))
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, points: &[ControlPoint], viewport: &Viewport) {
+        let (left, top) = Self::corner();
+        let (right, bottom) = (left + MAP_WIDTH - 1, top + MAP_HEIGHT - 1);
+        for y in top..=bottom {
+            for x in left..=right {
+                blend_point(canvas, x, y, PANEL_COLOR, PANEL_ALPHA);
+            }
+        }
+        draw_rect(canvas, left, top, right, bottom, BORDER_COLOR);
+
+        let mapped: Vec<(i32, i32)> = points
+            .iter()
+            .map(|cp| {
+                let p = self.view.world_to_screen(cp.position);
+                (p.x.round() as i32, p.y.round() as i32)
+            })
+            .collect();
+        for pair in mapped.windows(2) {
+            draw_line(canvas, pair[0].0, pair[0].1, pair[1].0, pair[1].1, POLYGON_COLOR);
+        }
+        for &(x, y) in &mapped {
+            draw_point(canvas, x, y, POINT_COLOR);
+        }
+
+        // The window's outline in map pixels, pinned to the map's border
+        // when the view has wandered off past the points
+        let corner = |screen: Point2<f32>| {
+            let p = self.view.world_to_screen(viewport.screen_to_world(screen));
+            (
+                (p.x.round() as i32).clamp(left, right),
+                (p.y.round() as i32).clamp(top, bottom),
+            )
+        };
+        let (x0, y0) = corner(Point2::origin());
+        let (x1, y1) = corner(Point2::new(WIDTH as f32, HEIGHT as f32));
+        draw_rect(canvas, x0, y0, x1, y1, VIEW_COLOR);
+    }
+}
+
+fn draw_rect(canvas: &mut Canvas, left: i32, top: i32, right: i32, bottom: i32, color: u32) {
+    draw_line(canvas, left, top, right, top, color);
+    draw_line(canvas, right, top, right, bottom, color);
+    draw_line(canvas, right, bottom, left, bottom, color);
+    draw_line(canvas, left, bottom, left, top, color);
+}
@@ -0,0 +1,47 @@
+use std::time::{Duration, Instant};
+
+// Pause after the first nudge before a held key starts repeating
+const REPEAT_DELAY: Duration = Duration::from_millis(250);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(30);
+// A held key speeds up from 1 px per repeat to this over ACCELERATION_TIME
+const MAX_NUDGE: f32 = 8.0;
+const ACCELERATION_TIME: Duration = Duration::from_millis(1500);
+
+// Turns a held key into a stream of nudges: one straight away, then a
+// steady repeat that moves further the longer the key stays down
+pub struct KeyRepeater {
+    pressed_at: Option<Instant>,
+    next_fire: Instant,
+}
+
+impl KeyRepeater {
+    pub fn new() -> Self {
+        Self {
+            pressed_at: None,
+            next_fire: Instant::now(),
+        }
+    }
+
+    // Returns how many pixels to move this frame, if any
+    pub fn update(&mut self, down: bool) -> Option<f32> {
+        if !down {
+            self.pressed_at = None;
+            return None;
+        }
+
+        let now = Instant::now();
+        let Some(pressed_at) = self.pressed_at else {
+            self.pressed_at = Some(now);
+            self.next_fire = now + REPEAT_DELAY;
+            return Some(1.0);
+        };
+
+        if now < self.next_fire {
+            return None;
+        }
+        self.next_fire = now + REPEAT_INTERVAL;
+        let held = (now - pressed_at).saturating_sub(REPEAT_DELAY);
+        let t = (held.as_secs_f32() / ACCELERATION_TIME.as_secs_f32()).min(1.0);
+        Some(1.0 + (MAX_NUDGE - 1.0) * t)
+    }
+}
@@ -0,0 +1,117 @@
+use chaikin::ControlPoint;
+
+use crate::draw::{Canvas, blend_point, draw_line};
+use crate::text::{GLYPH_HEIGHT, draw_text};
+use crate::WIDTH;
+
+const PANEL_WIDTH: i32 = 96;
+const PANEL_TOP: i32 = 24;
+const PANEL_MARGIN: i32 = 6;
+const PADDING: i32 = 4;
+const ROW_HEIGHT: i32 = GLYPH_HEIGHT + 3;
+const PANEL_COLOR: u32 = 0xFF101018;
+const PANEL_ALPHA: f32 = 0.85;
+const ROW_COLOR: u32 = 0xFFCCCCCC;
+const GRABBED_COLOR: u32 = 0xFFFFD060;
+const DROP_MARKER_COLOR: u32 = 0xFFFFD060;
+
+// Side panel listing the control points in traversal order. Dragging a row
+// onto another slot moves that point there.
+pub struct PointList {
+    // Row picked up by the current drag
+    grabbed: Option<usize>,
+    // Slot the grabbed row would be dropped into
+    target: Option<usize>,
+}
+
+impl PointList {
+    pub fn new() -> Self {
+        Self {
+            grabbed: None,
+            target: None,
+        }
+    }
+
+    fn left() -> i32 {
+        WIDTH as i32 - PANEL_MARGIN - PANEL_WIDTH
+    }
+
+    fn height(count: usize) -> i32 {
+        count as i32 * ROW_HEIGHT + PADDING * 2
+    }
+
+    pub fn is_over(&self, x: f32, y: f32, count: usize) -> bool {
+        let (x, y) = (x as i32, y as i32);
+        x >= Self::left()
+            && x < Self::left() + PANEL_WIDTH
+            && y >= PANEL_TOP
+            && y < PANEL_TOP + Self::height(count)
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.grabbed.is_some()
+    }
+
+    // Row under screen height `y`, clamped to the rows that exist
+    fn slot_at(y: f32, count: usize) -> usize {
+        let row = (y as i32 - PANEL_TOP - PADDING).div_euclid(ROW_HEIGHT);
+        row.clamp(0, count as i32 - 1) as usize
+    }
+
+    // Feeds the left button to the list. Returns the (from, to) indices once a
+    // row is dropped on a different slot.
+    pub fn update(
+        &mut self,
+        pos: Option<(f32, f32)>,
+        down: bool,
+        count: usize,
+    ) -> Option<(usize, usize)> {
+        if count == 0 {
+            self.grabbed = None;
+            self.target = None;
+            return None;
+        }
+
+        if down {
+            if let Some((x, y)) = pos {
+                if self.grabbed.is_none() && self.is_over(x, y, count) {
+                    self.grabbed = Some(Self::slot_at(y, count));
+                }
+                if self.grabbed.is_some() {
+                    self.target = Some(Self::slot_at(y, count));
+                }
+            }
+            return None;
+        }
+
+        let from = self.grabbed.take()?;
+        let to = self.target.take()?;
+        (from != to).then_some((from, to))
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, points: &[ControlPoint]) {
+        let left = Self::left();
+        for y in PANEL_TOP..PANEL_TOP + Self::height(points.len()) {
+            for x in left..left + PANEL_WIDTH {
+                blend_point(canvas, x, y, PANEL_COLOR, PANEL_ALPHA);
+            }
+        }
+
+        for (i, point) in points.iter().enumerate() {
+            let y = PANEL_TOP + PADDING + i as i32 * ROW_HEIGHT;
+            let text = format!("{:>2} {:.0},{:.0}", i, point.position.x, point.position.y);
+            let color = if self.grabbed == Some(i) { GRABBED_COLOR } else { ROW_COLOR };
+            draw_text(canvas, left + PADDING, y + 1, &text, color);
+        }
+
+        // Line above or below the target row, on the side the point will land
+        if let (Some(from), Some(to)) = (self.grabbed, self.target)
+            && from != to
+        {
+            let row = if to > from { to + 1 } else { to };
+            let y = PANEL_TOP + PADDING + row as i32 * ROW_HEIGHT - 1;
+            let right = left + PANEL_WIDTH - PADDING;
+            draw_line(canvas, left + PADDING, y, right, y, DROP_MARKER_COLOR);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::time::Instant;
+
+use nalgebra::Point2;
+
+// A stroke drawn at this speed (in window pixels per second) gets the
+// normal weight of 1; slower is heavier and faster lighter
+const REFERENCE_SPEED: f32 = 400.0;
+const MIN_WEIGHT: f32 = 0.5;
+const MAX_WEIGHT: f32 = 1.6;
+
+// Where the weight of a freehand point comes from. The weight scales the
+// ratio of the segment leading to the point, so a heavier point rounds its
+// corner off more. minifb reports no pen pressure, so a tablet would slot
+// in here as another source; until then the drag speed can stand in for it.
+pub enum WeightSource {
+    // Every point weighs 1, leaving the ratios alone
+    Mouse,
+    // Slow strokes weigh more than fast ones. Keeps where and when the
+    // previous point of the stroke was placed.
+    Speed(Option<(Point2<f32>, Instant)>),
+}
+
+impl WeightSource {
+    pub fn new(from_speed: bool) -> Self {
+        if from_speed {
+            WeightSource::Speed(None)
+        } else {
+            WeightSource::Mouse
+        }
+    }
+
+    // The weight of a point placed now at `position` (in window pixels)
+    pub fn weight(&mut self, position: Point2<f32>) -> f32 {
+        let WeightSource::Speed(last) = self else {
+            return 1.0;
+        };
+        let now = Instant::now();
+        match last.replace((position, now)) {
+            Some((previous, at)) => {
+                let seconds = now.duration_since(at).as_secs_f32();
+                if seconds > 0.0 {
+                    let speed = (position - previous).norm() / seconds;
+                    (REFERENCE_SPEED / speed).clamp(MIN_WEIGHT, MAX_WEIGHT)
+                } else {
+                    1.0
+                }
+            }
+            // The first point of a stroke has no speed yet
+            None => 1.0,
+        }
+    }
+
+    // Forgets the previous point once a stroke ends
+    pub fn end_stroke(&mut self) {
+        if let WeightSource::Speed(last) = self {
+            *last = None;
+        }
+    }
+}
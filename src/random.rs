@@ -0,0 +1,52 @@
+use std::f32::consts::TAU;
+
+use nalgebra::{Point2, Vector2};
+
+use crate::{HEIGHT, WIDTH};
+
+// Space kept between the blob and the window's edges
+const BLOB_MARGIN: f32 = 40.0;
+// How far in from the outer radius a point may land, as a fraction of it
+const BLOB_DENT: f32 = 0.55;
+// How far a point's angle may drift from its even share of the circle, as
+// a fraction of that share; below 1 the points stay in order around it
+const ANGLE_JITTER: f32 = 0.8;
+
+// SplitMix64: tiny, seedable with any value, and plenty random enough for
+// scattering demo points
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+// `count` points going once round the middle of the window at random
+// distances, so joining them up in order makes a closed blob that doesn't
+// cross itself. Every point stays inside the window.
+pub fn random_blob(count: usize, seed: u64) -> Vec<Point2<f32>> {
+    let mut rng = Rng::new(seed);
+    let center = Point2::new(WIDTH as f32 / 2.0, HEIGHT as f32 / 2.0);
+    let radius = WIDTH.min(HEIGHT) as f32 / 2.0 - BLOB_MARGIN;
+    (0..count)
+        .map(|i| {
+            let angle = (i as f32 + rng.next_f32() * ANGLE_JITTER) / count as f32 * TAU;
+            let distance = radius * (1.0 - BLOB_DENT * rng.next_f32());
+            center + Vector2::new(angle.cos(), angle.sin()) * distance
+        })
+        .collect()
+}
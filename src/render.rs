@@ -0,0 +1,188 @@
+use nalgebra::Point2;
+
+use crate::curve::{ChaikinCurve, POINT_RADIUS};
+
+const CURVE_COLOR: u32 = 0xFF00FF00;
+const POINT_COLOR: u32 = 0xFFFFFFFF;
+// Size of the dot drawn for a curve of a single point
+const SINGLE_POINT_RADIUS: i32 = 3;
+
+/// A pixel buffer along with its size: `height` rows of `width` 0RGB pixels.
+/// Anything drawn outside it is clipped.
+pub struct Canvas<'a> {
+    pub pixels: &'a mut [u32],
+    pub width: usize,
+    pub height: usize,
+}
+
+impl<'a> Canvas<'a> {
+    /// Panics unless `pixels` holds exactly `width` x `height` pixels.
+    pub fn new(pixels: &'a mut [u32], width: usize, height: usize) -> Self {
+        assert_eq!(pixels.len(), width * height, "the buffer isn't {}x{}", width, height);
+        Self { pixels, width, height }
+    }
+
+    // Index of (`x`, `y`) in `pixels`, or `None` outside the canvas
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        (x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32)
+            .then(|| y as usize * self.width + x as usize)
+    }
+}
+
+/// Sets the pixel at (`x`, `y`), if it is on the canvas.
+pub fn draw_point(canvas: &mut Canvas, x: i32, y: i32, color: u32) {
+    if let Some(index) = canvas.index(x, y) {
+        canvas.pixels[index] = color;
+    }
+}
+
+/// Mixes `color` into the pixel at (`x`, `y`) with the given opacity, if it
+/// is on the canvas.
+pub fn blend_point(canvas: &mut Canvas, x: i32, y: i32, color: u32, alpha: f32) {
+    if let Some(index) = canvas.index(x, y) {
+        canvas.pixels[index] = blend_color(canvas.pixels[index], color, alpha);
+    }
+}
+
+/// Mixes `src` over `dst` with the given opacity (0.0 keeps `dst`, 1.0 gives
+/// `src`).
+pub fn blend_color(dst: u32, src: u32, alpha: f32) -> u32 {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let channel = |shift: u32| {
+        let d = ((dst >> shift) & 0xFF) as f32;
+        let s = ((src >> shift) & 0xFF) as f32;
+        ((d + (s - d) * alpha).round() as u32) << shift
+    };
+    0xFF000000 | channel(16) | channel(8) | channel(0)
+}
+
+/// Filled disc of pixels within `radius` of the centre.
+pub fn draw_circle(canvas: &mut Canvas, center_x: i32, center_y: i32, radius: i32, color: u32) {
+    for y in -radius..=radius {
+        for x in -radius..=radius {
+            if x * x + y * y <= radius * radius {
+                draw_point(canvas, center_x + x, center_y + y, color);
+            }
+        }
+    }
+}
+
+/// Bresenham line between two pixels, both ends included.
+pub fn draw_line(canvas: &mut Canvas, x0: i32, y0: i32, x1: i32, y1: i32, color: u32) {
+    let dx = (x1 - x0).abs();
+    let dy = -(y1 - y0).abs();
+
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let sy = if y0 < y1 { 1 } else { -1 };
+
+    let mut err = dx + dy;
+    let mut x = x0;
+    let mut y = y0;
+
+    loop {
+        draw_point(canvas, x, y, color);
+        if x == x1 && y == y1 {
+            break;
+        }
+
+        let e2 = 2 * err;
+        if e2 >= dy {
+            if x == x1 {
+                break;
+            }
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            if y == y1 {
+                break;
+            }
+            err += dx;
+            y += sy;
+        }
+    }
+}
+
+/// Line between two float positions, rounded to the nearest pixels.
+pub fn draw_segment(canvas: &mut Canvas, a: Point2<f32>, b: Point2<f32>, color: u32) {
+    draw_line(
+        canvas,
+        a.x.round() as i32,
+        a.y.round() as i32,
+        b.x.round() as i32,
+        b.y.round() as i32,
+        color,
+    );
+}
+
+/// Joins each point of `points` to the next. Fewer than two draw nothing.
+pub fn draw_polyline(canvas: &mut Canvas, points: &[Point2<f32>], color: u32) {
+    for segment in points.windows(2) {
+        draw_segment(canvas, segment[0], segment[1], color);
+    }
+}
+
+impl ChaikinCurve {
+    /// Draws the curve as it currently stands (the animation step on show,
+    /// or the control polygon) in green, with the control points as white
+    /// dots on top, into `buffer`: `width` x `height` 0RGB pixels, row by row,
+    /// in the curve's own coordinates. Only those pixels are written, so the
+    /// drawing can go over whatever the buffer already holds, and anything
+    /// outside it is clipped. Needs no window, for embedding the view
+    /// elsewhere; the interactive app draws with the same primitives.
+    pub fn render(&self, buffer: &mut [u32], width: usize, height: usize) {
+        let mut canvas = Canvas::new(buffer, width, height);
+
+        let mut curve = self.get_current_points();
+        if self.is_closed()
+            && self.revealed_points.is_none()
+            && let Some(&first) = curve.first()
+        {
+            curve.push(first);
+        }
+        if let [only] = curve[..] {
+            let (x, y) = (only.x.round() as i32, only.y.round() as i32);
+            draw_circle(&mut canvas, x, y, SINGLE_POINT_RADIUS, CURVE_COLOR);
+        } else {
+            draw_polyline(&mut canvas, &curve, CURVE_COLOR);
+        }
+
+        for point in self.visible_control_points() {
+            let (x, y) = (point.position.x.round() as i32, point.position.y.round() as i32);
+            draw_circle(&mut canvas, x, y, POINT_RADIUS, POINT_COLOR);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_lights_the_line_between_the_points() {
+        let mut curve = ChaikinCurve::new();
+        curve.add_point(2.0, 4.0);
+        curve.add_point(30.0, 4.0);
+        curve.add_point(30.0, 18.0);
+        let (width, height) = (40, 24);
+        let mut buffer = vec![0; width * height];
+        curve.render(&mut buffer, width, height);
+        let pixel = |x: usize, y: usize| buffer[y * width + x];
+
+        // Along both sides of the polygon, clear of the point dots
+        for x in 8..=24 {
+            assert_eq!(pixel(x, 4), CURVE_COLOR, "({}, 4)", x);
+        }
+        for y in 10..=12 {
+            assert_eq!(pixel(30, y), CURVE_COLOR, "(30, {})", y);
+        }
+        for (x, y) in [(2, 4), (30, 4), (30, 18)] {
+            assert_eq!(pixel(x, y), POINT_COLOR);
+        }
+        // Nothing off the lines, and the open curve isn't closed up
+        assert_eq!(pixel(16, 12), 0);
+        assert_eq!(pixel(16, 11), 0);
+        let lit = buffer.iter().filter(|&&p| p == CURVE_COLOR).count();
+        assert!(lit < 60, "{} curve pixels", lit);
+    }
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chaikin::ChaikinCurve;
+use serde::{Deserialize, Serialize};
+
+use crate::actions::Action;
+
+pub const SESSION_FILE: &str = "chaikin_session.json";
+
+// On-disk layout of a recorded session: the points it started from, then
+// every action with the time since the one before it.
+// { "closed": false, "points": [ [x, y], ... ],
+//   "events": [ { "after_ms": 250, "action": { "AddPoint": { ... } } }, ... ] }
+#[derive(Debug, Serialize, Deserialize)]
+struct Session {
+    closed: bool,
+    points: Vec<[f32; 2]>,
+    events: Vec<Event>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Event {
+    after_ms: u64,
+    action: Action,
+}
+
+// Collects the actions taken while recording, with their pacing
+pub struct Recorder {
+    session: Session,
+    last: Instant,
+}
+
+impl Recorder {
+    pub fn start(chaikin: &ChaikinCurve) -> Self {
+        Self {
+            session: Session {
+                closed: chaikin.closed,
+                points: chaikin
+                    .control_points
+                    .iter()
+                    .map(|cp| [cp.position.x, cp.position.y])
+                    .collect(),
+                events: Vec::new(),
+            },
+            last: Instant::now(),
+        }
+    }
+
+    pub fn record(&mut self, action: Action) {
+        let now = Instant::now();
+        let after_ms = now.duration_since(self.last).as_millis() as u64;
+        self.last = now;
+        self.session.events.push(Event { after_ms, action });
+    }
+
+    pub fn len(&self) -> usize {
+        self.session.events.len()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, serde_json::to_string_pretty(&self.session)?)
+    }
+}
+
+// Feeds a recorded session back in as if the actions were happening again
+pub struct Player {
+    session: Session,
+    next: usize,
+    // When the next action is due
+    due: Instant,
+}
+
+impl Player {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        let session: Session = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let first = session.events.first().map_or(0, |event| event.after_ms);
+        Ok(Self {
+            session,
+            next: 0,
+            due: Instant::now() + Duration::from_millis(first),
+        })
+    }
+
+    // Puts the curve back to the points the recording started from
+    pub fn reset(&self, chaikin: &mut ChaikinCurve) {
+        chaikin.clear_points();
+        for &[x, y] in &self.session.points {
+            chaikin.add_point(x, y);
+        }
+        chaikin.closed = self.session.closed;
+    }
+
+    // The actions whose time has come, in the order they were recorded
+    pub fn due(&mut self) -> Vec<Action> {
+        let mut actions = Vec::new();
+        let now = Instant::now();
+        while let Some(event) = self.session.events.get(self.next) {
+            if now < self.due {
+                break;
+            }
+            actions.push(event.action);
+            self.next += 1;
+            if let Some(next) = self.session.events.get(self.next) {
+                self.due += Duration::from_millis(next.after_ms);
+            }
+        }
+        actions
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.session.events.len()
+    }
+}
@@ -0,0 +1,87 @@
+use nalgebra::{Point2, Vector2};
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 20.0;
+
+// Maps the curve's own (world) coordinates to window pixels:
+// screen = world * zoom + offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Viewport {
+    pub offset: Vector2<f32>,
+    pub zoom: f32,
+}
+
+impl Viewport {
+    pub fn new() -> Self {
+        Self {
+            offset: Vector2::zeros(),
+            zoom: 1.0,
+        }
+    }
+
+    pub fn world_to_screen(&self, p: Point2<f32>) -> Point2<f32> {
+        p * self.zoom + self.offset
+    }
+
+    pub fn screen_to_world(&self, p: Point2<f32>) -> Point2<f32> {
+        (p - self.offset) / self.zoom
+    }
+
+    // The same view onto a buffer `factor` times the window's size
+    pub fn scaled(&self, factor: f32) -> Self {
+        Self {
+            offset: self.offset * factor,
+            zoom: self.zoom * factor,
+        }
+    }
+
+    pub fn points_to_screen(&self, points: &[Point2<f32>]) -> Vec<Point2<f32>> {
+        points.iter().map(|&p| self.world_to_screen(p)).collect()
+    }
+
+    // Moves the view by a distance in screen pixels
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        self.offset += Vector2::new(dx, dy);
+    }
+
+    // Pans so that `world` ends up at the screen position `screen`
+    pub fn center_on(&mut self, world: Point2<f32>, screen: Point2<f32>) {
+        self.offset = screen.coords - world.coords * self.zoom;
+    }
+
+    // Brings all of `points` into a `width` x `height` window, at least
+    // `margin` pixels in from its edges, centred on them. The view only
+    // zooms out as far as it has to, never in.
+    pub fn fit(&mut self, points: &[Point2<f32>], width: f32, height: f32, margin: f32) {
+        let Some(&first) = points.first() else {
+            return;
+        };
+        let (min, max) = points
+            .iter()
+            .fold((first, first), |(min, max), p| (min.inf(p), max.sup(p)));
+        let extent = (max - min).map(|v| v.max(1.0));
+        let fitting = ((width - margin * 2.0) / extent.x).min((height - margin * 2.0) / extent.y);
+        self.zoom = self.zoom.min(fitting).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.center_on(nalgebra::center(&min, &max), Point2::new(width / 2.0, height / 2.0));
+    }
+
+    // Moves the fraction `t` of the way to `target`, zooming by ratios so a
+    // run of scroll notches eases evenly, and lands on it once what is left
+    // is too small to see
+    pub fn ease_toward(&mut self, target: &Viewport, t: f32) {
+        self.zoom *= (target.zoom / self.zoom).powf(t);
+        self.offset = self.offset.lerp(&target.offset, t);
+        let zoom_left = (self.zoom / target.zoom - 1.0).abs();
+        if zoom_left < 1e-4 && (self.offset - target.offset).norm() < 0.1 {
+            *self = *target;
+        }
+    }
+
+    // Zooms by `factor` while keeping whatever is under `anchor` (a screen
+    // position, usually the cursor) in place
+    pub fn zoom_at(&mut self, anchor: Point2<f32>, factor: f32) {
+        let world = self.screen_to_world(anchor);
+        self.zoom = (self.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        self.offset = anchor.coords - world.coords * self.zoom;
+    }
+}